@@ -0,0 +1,26 @@
+use crate::Value;
+
+/// Host game side of `do` statements. Set with `Runner::set_command_handler()`.
+pub trait CommandHandler {
+    /// Called when the runner executes `do name(arguments);`.
+    fn command(&mut self, name: &str, arguments: &[Value]);
+}
+
+/// Closures can be used as handlers as well.
+impl<F: FnMut(&str, &[Value])> CommandHandler for F {
+    fn command(&mut self, name: &str, arguments: &[Value]) {
+        self(name, arguments)
+    }
+}
+
+/// Command as it's written in the script, such as `do play("door.ogg")`.
+pub(crate) fn command_text(name: &str, arguments: &[Value]) -> String {
+    let arguments: Vec<String> = arguments
+        .iter()
+        .map(|a| match a {
+            Value::String(s) => format!("\"{}\"", s),
+            _ => a.to_string(),
+        })
+        .collect();
+    format!("do {}({})", name, arguments.join(", "))
+}
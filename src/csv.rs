@@ -0,0 +1,200 @@
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::{Dialogue, DialogueEntry, ParseError};
+
+/// Columns of dialogue spreadsheet, in order.
+pub const CSV_COLUMNS: [&str; 5] = ["id", "label", "speakers", "text", "target"];
+
+/// Column which can be edited in the spreadsheet.
+const TEXT_COLUMN: usize = 3;
+
+#[derive(Error, Debug)]
+pub enum CsvError {
+    #[error("Unterminated quoted field in row {0}")]
+    UnterminatedField(usize),
+    #[error("Invalid header, expected columns {}", CSV_COLUMNS.join(", "))]
+    InvalidHeader,
+    #[error("Row {row} should have {} columns, got {found}", CSV_COLUMNS.len())]
+    ColumnCount { row: usize, found: usize },
+    #[error("Rows were added or removed, expected {expected} rows, got {found}")]
+    RowCount { expected: usize, found: usize },
+    #[error("Column '{column}' of row {row} was changed, only texts can be edited")]
+    StructureChanged { row: usize, column: &'static str },
+    #[error("Edited script is invalid: {0}")]
+    InvalidScript(#[from] ParseError),
+}
+
+/// Text as writers see it. Quotes are escaped in the script, but not in the spreadsheet.
+fn to_csv_text(text: &str) -> String {
+    text.replace("\\\"", "\"")
+}
+
+/// Reverses `to_csv_text()`.
+fn from_csv_text(text: &str) -> String {
+    text.replace('"', "\\\"")
+}
+
+/// Quotes field if it contains delimiter, quotes or line breaks.
+pub(crate) fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits spreadsheet into rows of fields. Quoted fields may contain delimiters,
+/// line breaks and doubled quotes. Rows are counted from 1 in errors, like spreadsheets do.
+fn parse_rows(src: &str, delimiter: char) -> Result<Vec<Vec<String>>, CsvError> {
+    let src = src.strip_prefix('\u{feff}').unwrap_or(src);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(CsvError::UnterminatedField(rows.len() + 1));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    while rows.last().is_some_and(|r| r.iter().all(String::is_empty)) {
+        rows.pop();
+    }
+    Ok(rows)
+}
+
+impl Dialogue {
+    /// Rows of the spreadsheet, with ranges of their texts in the source.
+    fn csv_rows(&self) -> Vec<([String; 5], Range<usize>)> {
+        let mut rows = Vec::new();
+        for (index, entry) in self.entries().iter().enumerate() {
+            let label = self.section(index).unwrap_or_default().to_string();
+            let range = |option: usize| {
+                self.meta(index)
+                    .and_then(|m| m.ranges.get(option))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            match entry {
+                DialogueEntry::Phrase(h, t) => {
+                    let speakers: Vec<String> = h.iter().map(|h| format!("@{}", h)).collect();
+                    rows.push((
+                        [
+                            self.text_key(index, 0).unwrap_or_default(),
+                            label,
+                            speakers.join(" & "),
+                            to_csv_text(t),
+                            String::new(),
+                        ],
+                        range(0),
+                    ));
+                }
+                DialogueEntry::Choice(c) => {
+                    for (option, choice) in c.iter().enumerate() {
+                        rows.push((
+                            [
+                                self.text_key(index, option).unwrap_or_default(),
+                                label.clone(),
+                                String::new(),
+                                to_csv_text(&choice.text),
+                                choice.label.clone(),
+                            ],
+                            range(option),
+                        ));
+                    }
+                }
+                _ => (),
+            }
+        }
+        rows
+    }
+
+    /// Writes phrases and choice options as spreadsheet with `CSV_COLUMNS`.
+    /// Delimiter is `,` for CSV and `\t` for TSV.
+    /// Choice options have the label they jump to as target.
+    pub fn to_csv(&self, delimiter: char) -> String {
+        let mut result = CSV_COLUMNS.join(&delimiter.to_string());
+        result.push('\n');
+        for (row, _) in self.csv_rows() {
+            let fields: Vec<String> = row.iter().map(|f| quote(f, delimiter)).collect();
+            result.push_str(&fields.join(&delimiter.to_string()));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Applies texts edited in the spreadsheet to the source of the script.
+    /// Only edited texts are replaced, the rest of the source is kept as is.
+    /// Fails if rows were added, removed or reordered, or any other column was changed.
+    pub fn apply_csv(src: &str, csv: &str, delimiter: char) -> Result<String, CsvError> {
+        let expected = Dialogue::parse(src)?.csv_rows();
+        let rows = parse_rows(csv, delimiter)?;
+        let Some((header, rows)) = rows.split_first() else {
+            return Err(CsvError::InvalidHeader);
+        };
+        if header.iter().map(String::as_str).ne(CSV_COLUMNS) {
+            return Err(CsvError::InvalidHeader);
+        }
+        if rows.len() != expected.len() {
+            return Err(CsvError::RowCount {
+                expected: expected.len(),
+                found: rows.len(),
+            });
+        }
+        let mut edits = Vec::new();
+        for (n, (row, (cells, range))) in rows.iter().zip(expected).enumerate() {
+            // Header is the first row.
+            let number = n + 2;
+            if row.len() != CSV_COLUMNS.len() {
+                return Err(CsvError::ColumnCount {
+                    row: number,
+                    found: row.len(),
+                });
+            }
+            if let Some(column) =
+                (0..CSV_COLUMNS.len()).find(|c| *c != TEXT_COLUMN && row[*c] != cells[*c])
+            {
+                return Err(CsvError::StructureChanged {
+                    row: number,
+                    column: CSV_COLUMNS[column],
+                });
+            }
+            if row[TEXT_COLUMN] != cells[TEXT_COLUMN] {
+                edits.push((range, from_csv_text(&row[TEXT_COLUMN])));
+            }
+        }
+        let mut result = src.to_string();
+        for (range, text) in edits.into_iter().rev() {
+            result.replace_range(range, &text);
+        }
+        Dialogue::parse(&result)?;
+        Ok(result)
+    }
+}
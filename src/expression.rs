@@ -0,0 +1,278 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use pest::iterators::Pair;
+use thiserror::Error;
+
+use crate::{Dialogue, DialogueEntry, Rule, TextPart, Value, ValueType};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ExpressionError {
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+    #[error("Function '{name}' expects {expected} arguments, got {found}")]
+    Arity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Argument {index} of function '{name}' should be {expected}")]
+    ArgumentType {
+        name: String,
+        index: usize,
+        expected: ValueType,
+    },
+    #[error("Function '{name}' should return {expected}, returned {found}")]
+    ReturnType {
+        name: String,
+        expected: ValueType,
+        found: ValueType,
+    },
+    #[error("Undefined variable '{0}'")]
+    UndefinedVariable(String),
+    #[error("Expected {expected}, got {found}")]
+    Type {
+        expected: ValueType,
+        found: ValueType,
+    },
+}
+
+/// Expression evaluated by the runner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Literal(Value),
+    /// Variable set by the host. Example: $gold
+    Variable(String),
+    /// Call of a function registered by the host. Example: has_item("key")
+    Call(String, Vec<Expression>),
+    /// Boolean negation. Example: !has_item("key")
+    Not(Box<Expression>),
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Literal(Value::String(s)) => write!(f, "\"{}\"", s),
+            Expression::Literal(v) => write!(f, "{}", v),
+            Expression::Variable(n) => write!(f, "${}", n),
+            Expression::Call(n, a) => {
+                write!(f, "{}(", n)?;
+                for (i, a) in a.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", a)?;
+                }
+                write!(f, ")")
+            }
+            Expression::Not(e) => write!(f, "!{}", e),
+        }
+    }
+}
+
+type HostFunction<'a> = Rc<dyn Fn(&[Value]) -> Value + 'a>;
+
+/// Function registered by the host.
+#[derive(Clone)]
+struct Function<'a> {
+    parameters: Vec<ValueType>,
+    returns: ValueType,
+    body: HostFunction<'a>,
+}
+
+/// Functions implemented by the host, callable from expressions.
+/// Set with `Runner::set_functions()`. Clones share the function bodies.
+#[derive(Default, Clone)]
+pub struct FunctionRegistry<'a> {
+    functions: HashMap<String, Function<'a>>,
+}
+
+impl fmt::Debug for FunctionRegistry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+impl<'a> FunctionRegistry<'a> {
+    pub fn new() -> FunctionRegistry<'a> {
+        FunctionRegistry::default()
+    }
+
+    /// Registers function with types of its parameters and result.
+    /// Arguments are checked before the call, so `body` can rely on them.
+    pub fn register(
+        &mut self,
+        name: &str,
+        parameters: &[ValueType],
+        returns: ValueType,
+        body: impl Fn(&[Value]) -> Value + 'a,
+    ) {
+        self.functions.insert(
+            name.to_string(),
+            Function {
+                parameters: parameters.to_vec(),
+                returns,
+                body: Rc::new(body),
+            },
+        );
+    }
+
+    /// Types of parameters and result of the function.
+    pub fn signature(&self, name: &str) -> Option<(&[ValueType], ValueType)> {
+        self.functions
+            .get(name)
+            .map(|f| (f.parameters.as_slice(), f.returns))
+    }
+
+    /// Calls the function after checking its arguments, and checks type of the result.
+    pub fn call(&self, name: &str, arguments: &[Value]) -> Result<Value, ExpressionError> {
+        let types: Vec<Option<ValueType>> =
+            arguments.iter().map(|a| Some(a.value_type())).collect();
+        let returns = self.check_call(name, &types)?;
+        let result = (self.functions[name].body)(arguments);
+        if result.value_type() != returns {
+            return Err(ExpressionError::ReturnType {
+                name: name.to_string(),
+                expected: returns,
+                found: result.value_type(),
+            });
+        }
+        Ok(result)
+    }
+
+    /// Checks arguments against signature and returns type of the result.
+    /// Arguments of unknown type (variables) are not checked.
+    fn check_call(
+        &self,
+        name: &str,
+        arguments: &[Option<ValueType>],
+    ) -> Result<ValueType, ExpressionError> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| ExpressionError::UnknownFunction(name.to_string()))?;
+        if function.parameters.len() != arguments.len() {
+            return Err(ExpressionError::Arity {
+                name: name.to_string(),
+                expected: function.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        match function
+            .parameters
+            .iter()
+            .zip(arguments)
+            .position(|(p, a)| a.is_some_and(|a| a != *p))
+        {
+            Some(index) => Err(ExpressionError::ArgumentType {
+                name: name.to_string(),
+                index,
+                expected: function.parameters[index],
+            }),
+            None => Ok(function.returns),
+        }
+    }
+}
+
+impl Expression {
+    /// Builds expression from parsed pair.
+    pub(crate) fn from_pair(pair: Pair<'_, Rule>) -> Expression {
+        let inner = pair.into_inner().peek().unwrap();
+        match inner.as_rule() {
+            Rule::literal => Expression::Literal(Value::from_literal(inner)),
+            Rule::variable => {
+                Expression::Variable(inner.into_inner().peek().unwrap().as_str().to_string())
+            }
+            Rule::call => {
+                let mut inner = inner.into_inner();
+                let name = inner.next().unwrap().as_str().to_string();
+                Expression::Call(name, inner.map(Expression::from_pair).collect())
+            }
+            _ => Expression::Not(Box::new(Expression::from_pair(
+                inner.into_inner().peek().unwrap(),
+            ))),
+        }
+    }
+
+    /// Evaluates expression with variables and functions of the host.
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<String, Value>,
+        functions: &FunctionRegistry<'_>,
+    ) -> Result<Value, ExpressionError> {
+        match self {
+            Expression::Literal(v) => Ok(v.clone()),
+            Expression::Variable(n) => variables
+                .get(n)
+                .cloned()
+                .ok_or_else(|| ExpressionError::UndefinedVariable(n.clone())),
+            Expression::Call(n, a) => {
+                let arguments = a
+                    .iter()
+                    .map(|a| a.evaluate(variables, functions))
+                    .collect::<Result<Vec<Value>, _>>()?;
+                functions.call(n, &arguments)
+            }
+            Expression::Not(e) => match e.evaluate(variables, functions)? {
+                Value::Bool(b) => Ok(Value::Bool(!b)),
+                v => Err(ExpressionError::Type {
+                    expected: ValueType::Bool,
+                    found: v.value_type(),
+                }),
+            },
+        }
+    }
+
+    /// Checks function calls against their signatures and returns type of the expression.
+    /// Type of variables is not known until runtime, so it's `None`.
+    pub fn check(
+        &self,
+        functions: &FunctionRegistry<'_>,
+    ) -> Result<Option<ValueType>, ExpressionError> {
+        match self {
+            Expression::Literal(v) => Ok(Some(v.value_type())),
+            Expression::Variable(_) => Ok(None),
+            Expression::Call(n, a) => {
+                let arguments = a
+                    .iter()
+                    .map(|a| a.check(functions))
+                    .collect::<Result<Vec<_>, _>>()?;
+                functions.check_call(n, &arguments).map(Some)
+            }
+            Expression::Not(e) => {
+                expect_type(e.check(functions)?, ValueType::Bool)?;
+                Ok(Some(ValueType::Bool))
+            }
+        }
+    }
+}
+
+/// Fails if the type is known and differs from expected.
+fn expect_type(found: Option<ValueType>, expected: ValueType) -> Result<(), ExpressionError> {
+    match found {
+        Some(found) if found != expected => Err(ExpressionError::Type { expected, found }),
+        _ => Ok(()),
+    }
+}
+
+impl Dialogue {
+    /// Checks every expression against signatures of the host functions.
+    /// Conditions of jumps should be boolean, interpolated expressions can be of any type.
+    pub fn validate_functions(
+        &self,
+        functions: &FunctionRegistry<'_>,
+    ) -> Result<(), ExpressionError> {
+        for entry in self.entries() {
+            if let DialogueEntry::ConditionalJump(_, condition) = entry {
+                expect_type(condition.check(functions)?, ValueType::Bool)?;
+            }
+        }
+        for meta in &self.meta {
+            for part in meta.texts.iter().flatten() {
+                if let TextPart::Expression(e) = part {
+                    e.check(functions)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
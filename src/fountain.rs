@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use crate::{
+    Dialogue, DialogueEntry,
+    command::command_text,
+    markup::{replace_markup, strip_markup},
+};
+
+/// Markup tags shown as Fountain emphasis, with their markers.
+const EMPHASIS: &[(&str, &str)] = &[("b", "**"), ("i", "*"), ("u", "_")];
+
+/// Text of dialogue or action. Bold, italic and underlined markup becomes Fountain emphasis,
+/// other tags are removed. Blank lines would end the paragraph, so they are kept with spaces.
+fn fountain_text(text: &str) -> String {
+    replace_markup(text, EMPHASIS, |t| {
+        t.replace('*', "\\*").replace('_', "\\_")
+    })
+    .lines()
+    .map(|l| if l.trim().is_empty() { "  " } else { l })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Character cue. Cues are written in uppercase, names which can't be are forced with `@`.
+fn cue(names: &[String]) -> String {
+    let cue = names.join(" & ").to_uppercase();
+    if cue.chars().any(char::is_alphabetic) {
+        cue
+    } else {
+        format!("@{}", cue)
+    }
+}
+
+/// Whether action text would be taken for another element, and has to be forced with `!`:
+/// a character cue when in uppercase, a scene heading, a transition, a section, a synopsis,
+/// lyrics or a note.
+fn needs_forcing(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    let scene = [
+        "int.", "ext.", "est.", "int/ext", "i/e", "int ", "ext ", "est ",
+    ]
+    .iter()
+    .any(|p| lower.starts_with(p));
+    let forced_scene = text
+        .strip_prefix('.')
+        .is_some_and(|t| t.starts_with(char::is_alphanumeric));
+    let uppercase = text.chars().any(char::is_alphabetic) && text == text.to_uppercase();
+    scene
+        || forced_scene
+        || uppercase
+        || text.starts_with(['>', '#', '=', '~', '!', '@'])
+        || text.starts_with("[[")
+}
+
+/// Note with text which can't end it early. Fountain has no escape for `]]`, so the
+/// brackets are separated with a space.
+fn note(text: &str) -> String {
+    let mut text = text.to_string();
+    while text.contains("]]") {
+        text = text.replace("]]", "] ]");
+    }
+    if text.ends_with(']') {
+        text.push(' ');
+    }
+    format!("[[{}]]", text)
+}
+
+impl Dialogue {
+    /// Writes the dialogue as Fountain screenplay for reviews. Labels become scene headings,
+    /// phrases are dialogue with character cues from speaker names at that point, and
+    /// anonymous phrases are action. Choices, jumps and commands are shown as notes.
+    /// Metadata tags of phrases are shown as parentheticals.
+    pub fn to_fountain(&self, title: Option<&str>) -> String {
+        let mut labels: Vec<(&str, usize)> = self
+            .labels()
+            .iter()
+            .map(|(l, i)| (l.as_str(), *i))
+            .collect();
+        labels.sort_by_key(|(l, i)| (*i, *l));
+        let mut labels = labels.into_iter().peekable();
+        let mut names: HashMap<&str, &str> = HashMap::new();
+        let mut paragraphs = Vec::new();
+        if let Some(title) = title {
+            paragraphs.push(format!("Title: {}", title));
+        }
+        for index in 0..=self.len() {
+            while let Some((label, _)) = labels.next_if(|(_, i)| *i == index) {
+                paragraphs.push(format!(".{}", label));
+            }
+            let Some(entry) = self.get(index) else {
+                break;
+            };
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    names.insert(h, n);
+                }
+                DialogueEntry::Phrase(h, t) if h.is_empty() => {
+                    let text = fountain_text(&self.document_entry_text(index, 0, t, &names));
+                    if needs_forcing(&text) {
+                        paragraphs.push(format!("!{}", text));
+                    } else {
+                        paragraphs.push(text);
+                    }
+                }
+                DialogueEntry::Phrase(h, t) => {
+                    let speakers: Vec<String> = h
+                        .iter()
+                        .map(|h| names.get(h.as_str()).map_or(h.clone(), |n| n.to_string()))
+                        .collect();
+                    let mut paragraph = cue(&speakers);
+                    if let Some(tags) = self.tags(index).filter(|t| !t.is_empty()) {
+                        let tags: Vec<String> =
+                            tags.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                        paragraph.push_str(&format!("\n({})", tags.join(", ")));
+                    }
+                    paragraph.push('\n');
+                    paragraph.push_str(&fountain_text(
+                        &self.document_entry_text(index, 0, t, &names),
+                    ));
+                    paragraphs.push(paragraph);
+                }
+                DialogueEntry::Choice(c) => {
+                    let options: Vec<String> = c
+                        .iter()
+                        .enumerate()
+                        .map(|(option, choice)| {
+                            let text =
+                                self.document_entry_text(index, option, &choice.text, &names);
+                            format!("{}. {} → {}", option + 1, strip_markup(&text), choice.label)
+                        })
+                        .collect();
+                    paragraphs.push(note(&format!("Choice:\n{}", options.join("\n"))));
+                }
+                DialogueEntry::Jump(l) => paragraphs.push(note(&format!("Jump to {}", l))),
+                DialogueEntry::ConditionalJump(l, c) => {
+                    paragraphs.push(note(&format!("Jump to {} if {}", l, c)))
+                }
+                DialogueEntry::Command(n, a) => paragraphs.push(note(&command_text(n, a))),
+                DialogueEntry::AttributeChange(..) => (),
+            }
+        }
+        let mut result = paragraphs.join("\n\n");
+        result.push('\n');
+        result
+    }
+}
@@ -0,0 +1,197 @@
+use thiserror::Error;
+
+use crate::{StringEntry, StringKind, StringTable, source_hash};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PoError {
+    #[error("Invalid syntax at line {0}")]
+    InvalidSyntax(usize),
+    #[error("Unterminated string at line {0}")]
+    UnterminatedString(usize),
+}
+
+/// Quotes text as PO string. Texts keep escapes of the script, which are the same as in PO,
+/// so only characters which can't be written in PO as is are escaped. Texts from other
+/// sources can have quotes and backslashes which don't form escapes, they are escaped too.
+fn quote(text: &str) -> String {
+    let mut result = String::from("\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next_if(|c| !matches!(c, '\n' | '\t' | '\r')) {
+                Some(c) => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                None => result.push_str("\\\\"),
+            },
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Reverses `quote()` for content of PO string.
+fn unquote(line: &str, number: usize) -> Result<String, PoError> {
+    let content = line
+        .strip_prefix('"')
+        .and_then(|l| l.strip_suffix('"'))
+        .filter(|c| !c.ends_with('\\') || c.ends_with("\\\\"))
+        .ok_or(PoError::UnterminatedString(number))?;
+    let mut result = String::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c) => {
+                result.push('\\');
+                result.push(c);
+            }
+            None => result.push('\\'),
+        }
+    }
+    Ok(result)
+}
+
+/// Single entry of PO file.
+#[derive(Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: Option<String>,
+    text: Option<String>,
+    /// Fields which are read but not used, such as plural forms.
+    ignored: Option<String>,
+    fuzzy: bool,
+}
+
+impl StringTable {
+    /// Writes table as gettext template. Keys are used as `msgctxt`,
+    /// and source references point at lines of `path`.
+    pub fn to_pot(&self, path: &str) -> String {
+        let mut result = String::from(
+            "msgid \"\"\n\
+            msgstr \"\"\n\
+            \"MIME-Version: 1.0\\n\"\n\
+            \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+            \"Content-Transfer-Encoding: 8bit\\n\"\n",
+        );
+        if let Some(language) = &self.language {
+            result.push_str(&format!("\"Language: {}\\n\"\n", language));
+        }
+        for entry in &self.entries {
+            result.push('\n');
+            let speakers: Vec<String> = entry.speakers.iter().map(|h| format!("@{}", h)).collect();
+            match entry.kind {
+                StringKind::Phrase if !speakers.is_empty() => {
+                    result.push_str(&format!("#. {}\n", speakers.join(" & ")))
+                }
+                StringKind::Phrase => (),
+                StringKind::Choice => result.push_str("#. Choice option\n"),
+                StringKind::Name => {
+                    result.push_str(&format!("#. Name of {}\n", speakers.join(" & ")))
+                }
+            }
+            if let Some(line) = entry.line {
+                result.push_str(&format!("#: {}:{}\n", path, line));
+            }
+            result.push_str(&format!("msgctxt {}\n", quote(&entry.key)));
+            result.push_str(&format!("msgid {}\n", quote(&entry.text)));
+            result.push_str("msgstr \"\"\n");
+        }
+        result
+    }
+
+    /// Reads translated gettext file. Every string of `source` table is looked up by its key
+    /// in `msgctxt`. Strings which are missing, not translated or fuzzy keep the source text.
+    pub fn from_po(src: &str, source: &StringTable) -> Result<StringTable, PoError> {
+        Ok(source.translated(&StringTable::parse_po(src)?))
+    }
+
+    /// Reads strings translated in gettext file, skipping ones not translated or fuzzy.
+    /// Source hash is taken from `msgid`, which is the text the string was translated from.
+    /// Plural forms are not used by dialogues, only the first one is read.
+    pub fn parse_po(src: &str) -> Result<StringTable, PoError> {
+        let mut entries = Vec::new();
+        let mut entry = PoEntry::default();
+        // Field which continuation lines are appended to.
+        let mut field: Option<fn(&mut PoEntry) -> &mut Option<String>> = None;
+        for (number, line) in src.lines().enumerate().map(|(n, l)| (n + 1, l.trim())) {
+            if line.is_empty() {
+                entries.push(std::mem::take(&mut entry));
+                field = None;
+                continue;
+            }
+            // Entries don't need blank lines between them, next one starts after `msgstr`.
+            let starts_entry =
+                line.starts_with('#') || line.starts_with("msgctxt") || line.starts_with("msgid");
+            if starts_entry && entry.text.is_some() {
+                entries.push(std::mem::take(&mut entry));
+                field = None;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if comment.starts_with(',') && comment.contains("fuzzy") {
+                    entry.fuzzy = true;
+                }
+                continue;
+            }
+            if line.starts_with('"') {
+                let target = field.ok_or(PoError::InvalidSyntax(number))?(&mut entry);
+                target
+                    .get_or_insert_default()
+                    .push_str(&unquote(line, number)?);
+                continue;
+            }
+            let (keyword, rest) = line
+                .split_once(char::is_whitespace)
+                .ok_or(PoError::InvalidSyntax(number))?;
+            field = match keyword {
+                "msgctxt" => Some(|e| &mut e.context),
+                "msgid" => Some(|e| &mut e.id),
+                "msgstr" | "msgstr[0]" => Some(|e| &mut e.text),
+                "msgid_plural" => Some(|e| &mut e.ignored),
+                k if k.starts_with("msgstr[") && k.ends_with(']') => Some(|e| &mut e.ignored),
+                _ => return Err(PoError::InvalidSyntax(number)),
+            };
+            *field.unwrap()(&mut entry) = Some(unquote(rest.trim(), number)?);
+        }
+        entries.push(entry);
+
+        let header = entries
+            .iter()
+            .find(|e| e.context.is_none() && e.id.as_deref() == Some(""));
+        let language = header
+            .and_then(|h| h.text.as_deref())
+            .and_then(|t| t.lines().find_map(|l| l.strip_prefix("Language:")))
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        let entries = entries
+            .into_iter()
+            .filter(|e| !e.fuzzy)
+            .filter_map(|e| {
+                let key = e.context?;
+                let text = e.text.filter(|t| !t.is_empty())?;
+                Some(StringEntry {
+                    kind: StringKind::of_key(&key),
+                    key,
+                    text,
+                    speakers: Vec::new(),
+                    label: None,
+                    line: None,
+                    source_hash: e.id.as_deref().map(source_hash),
+                })
+            })
+            .collect();
+        Ok(StringTable { language, entries })
+    }
+}
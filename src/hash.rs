@@ -0,0 +1,143 @@
+use crate::{Dialogue, DialogueEntry, Expression, Value};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a hash. Unlike `std` hashers, it's guaranteed to stay the same between builds,
+/// so it can be stored in files.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> StableHasher {
+        StableHasher(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Writes string with its length, so "ab" + "c" and "a" + "bc" differ.
+    pub(crate) fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    pub(crate) fn write_value(&mut self, value: &Value) {
+        match value {
+            Value::Bool(b) => self.write(&[0, u8::from(*b)]),
+            Value::Number(n) => {
+                self.write(&[1]);
+                self.write(&n.to_le_bytes());
+            }
+            Value::String(s) => {
+                self.write(&[2]);
+                self.write_str(s);
+            }
+        }
+    }
+
+    pub(crate) fn write_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(v) => {
+                self.write(&[0]);
+                self.write_value(v);
+            }
+            Expression::Variable(n) => {
+                self.write(&[1]);
+                self.write_str(n);
+            }
+            Expression::Call(n, a) => {
+                self.write(&[2]);
+                self.write_str(n);
+                self.write(&(a.len() as u64).to_le_bytes());
+                a.iter().for_each(|a| self.write_expression(a));
+            }
+            Expression::Not(e) => {
+                self.write(&[3]);
+                self.write_expression(e);
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Dialogue {
+    /// Hash of entries, labels and explicit ids, as hex string. Changes whenever the script
+    /// changes in any way affecting execution or saved states, which refer to entries by id.
+    pub fn content_hash(&self) -> String {
+        let mut hasher = StableHasher::new();
+        for (index, entry) in self.entries().iter().enumerate() {
+            // Generated ids come from the entry itself, explicit ones have to be hashed.
+            match self
+                .meta(index)
+                .filter(|m| m.explicit_id)
+                .and_then(|m| m.id.as_deref())
+            {
+                Some(id) => {
+                    hasher.write(&[1]);
+                    hasher.write_str(id);
+                }
+                None => hasher.write(&[0]),
+            }
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    hasher.write(&[0]);
+                    hasher.write_str(h);
+                    hasher.write_str(n);
+                }
+                DialogueEntry::Phrase(h, t) => {
+                    hasher.write(&[1]);
+                    hasher.write(&(h.len() as u64).to_le_bytes());
+                    h.iter().for_each(|h| hasher.write_str(h));
+                    hasher.write_str(t);
+                }
+                DialogueEntry::Choice(c) => {
+                    hasher.write(&[2]);
+                    hasher.write(&(c.len() as u64).to_le_bytes());
+                    c.iter().for_each(|c| {
+                        hasher.write_str(&c.text);
+                        hasher.write_str(&c.label);
+                    });
+                }
+                DialogueEntry::Jump(l) => {
+                    hasher.write(&[3]);
+                    hasher.write_str(l);
+                }
+                DialogueEntry::ConditionalJump(l, c) => {
+                    hasher.write(&[5]);
+                    hasher.write_str(l);
+                    hasher.write_expression(c);
+                }
+                DialogueEntry::Command(n, a) => {
+                    hasher.write(&[4]);
+                    hasher.write_str(n);
+                    hasher.write(&(a.len() as u64).to_le_bytes());
+                    a.iter().for_each(|a| hasher.write_value(a));
+                }
+                DialogueEntry::AttributeChange(h, a) => {
+                    hasher.write(&[6]);
+                    hasher.write_str(h);
+                    hasher.write(&(a.len() as u64).to_le_bytes());
+                    a.iter().for_each(|(k, v)| {
+                        hasher.write_str(k);
+                        hasher.write_value(v);
+                    });
+                }
+            }
+        }
+        let mut labels: Vec<(&String, &usize)> = self.labels().iter().collect();
+        labels.sort();
+        for (label, index) in labels {
+            hasher.write_str(label);
+            hasher.write(&(*index as u64).to_le_bytes());
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
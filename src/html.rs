@@ -0,0 +1,102 @@
+use serde_json::{Value as Json, json};
+
+use crate::{Dialogue, DialogueEntry, Expression, TextPart};
+
+/// Page with the player. `{{title}}` and `{{dialogue}}` are replaced with the data.
+const PLAYER: &str = include_str!("player.html");
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn expression_json(expression: &Expression) -> Json {
+    match expression {
+        Expression::Literal(v) => json!({ "literal": v }),
+        Expression::Variable(n) => json!({ "variable": n }),
+        Expression::Call(n, a) => json!({
+            "call": n,
+            "arguments": a.iter().map(expression_json).collect::<Vec<_>>(),
+        }),
+        Expression::Not(e) => json!({ "not": expression_json(e) }),
+    }
+}
+
+/// Text parts for the player. Expressions keep their source, which is shown if they fail.
+fn text_json(parts: Option<&Vec<TextPart>>, source: &str) -> Json {
+    let Some(parts) = parts else {
+        return json!([{ "text": source }]);
+    };
+    parts
+        .iter()
+        .map(|p| match p {
+            TextPart::Text(t) => json!({ "text": t }),
+            TextPart::Speaker(h) => json!({ "speaker": h }),
+            TextPart::Expression(e) => json!({
+                "expression": expression_json(e),
+                "source": e.to_string(),
+            }),
+        })
+        .collect()
+}
+
+impl Dialogue {
+    /// Entries and labels in the form the HTML player runs them.
+    fn player_json(&self) -> Json {
+        let entries: Vec<Json> = self
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let texts = self.meta(index).map(|m| &m.texts);
+                let id = self.id(index);
+                match entry {
+                    DialogueEntry::NameChange(h, n) => {
+                        json!({ "kind": "name", "handle": h, "name": n })
+                    }
+                    DialogueEntry::AttributeChange(..) => json!({ "kind": "attributes" }),
+                    DialogueEntry::Phrase(h, t) => json!({
+                        "kind": "phrase",
+                        "id": id,
+                        "speakers": h,
+                        "text": text_json(texts.and_then(|t| t.first()), t),
+                    }),
+                    DialogueEntry::Choice(c) => json!({
+                        "kind": "choice",
+                        "id": id,
+                        "options": c.iter().enumerate().map(|(i, c)| json!({
+                            "text": text_json(texts.and_then(|t| t.get(i)), &c.text),
+                            "label": c.label,
+                        })).collect::<Vec<_>>(),
+                    }),
+                    DialogueEntry::Jump(l) => json!({ "kind": "jump", "label": l }),
+                    DialogueEntry::ConditionalJump(l, c) => json!({
+                        "kind": "conditional_jump",
+                        "label": l,
+                        "condition": expression_json(c),
+                    }),
+                    DialogueEntry::Command(..) => json!({ "kind": "command" }),
+                }
+            })
+            .collect();
+        json!({ "entries": entries, "labels": self.labels() })
+    }
+
+    /// Writes a single HTML page which plays the dialogue in the browser, without anything
+    /// else needed. The player follows the runner, without variables, functions and commands:
+    /// conditions which need them are false, and expressions in texts are shown as written.
+    pub fn to_html(&self, title: &str) -> String {
+        // Data is embedded in a script element, which must not be closed by any text.
+        let data = self.player_json().to_string().replace('<', "\\u003c");
+        let (head, tail) = PLAYER.split_once("{{dialogue}}").unwrap();
+        let title = escape(title);
+        format!(
+            "{}{}{}",
+            head.replace("{{title}}", &title),
+            data,
+            tail.replace("{{title}}", &title)
+        )
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Dialogue, DialogueEntry, ParseError, hash::StableHasher};
+
+/// Position in the dialogue which survives script edits. Created with `Dialogue::anchor()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    /// Id of the first phrase or choice at or after the position.
+    pub id: Option<String>,
+    /// Count of entries between the position and the entry with `id`.
+    pub before_id: usize,
+    /// Label of the section the position belongs to, `None` if it's before any label.
+    pub label: Option<String>,
+    /// Count of entries between the section start and the position.
+    pub offset: usize,
+}
+
+impl Dialogue {
+    /// Label of the section the entry belongs to, which is the closest label at or before it.
+    /// If several labels point at the same entry, the first one alphabetically is used.
+    pub fn section(&self, index: usize) -> Option<&str> {
+        self.labels()
+            .iter()
+            .filter(|(_, i)| **i <= index)
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(l, _)| l.as_str())
+    }
+
+    /// Stable id of phrase or choice. `None` for other entries.
+    pub fn id(&self, index: usize) -> Option<&str> {
+        self.meta.get(index).and_then(|m| m.id.as_deref())
+    }
+
+    /// Index of the entry with the id.
+    pub fn find_id(&self, id: &str) -> Option<usize> {
+        self.ids.get(id).copied()
+    }
+
+    /// Anchor of the position, which can be resolved in edited version of the dialogue.
+    pub fn anchor(&self, index: usize) -> Anchor {
+        let next = (index..self.len()).find(|i| self.id(*i).is_some());
+        let label = self.section(index);
+        let start = label.and_then(|l| self.label(l)).unwrap_or(0);
+        Anchor {
+            id: next.and_then(|i| self.id(i)).map(str::to_string),
+            before_id: next.map_or(0, |i| i - index),
+            label: label.map(str::to_string),
+            offset: index - start,
+        }
+    }
+
+    /// Finds position closest to the anchor. Entry with the same id is preferred,
+    /// otherwise the same offset in the same section is used.
+    /// `None` if neither id nor section exist anymore.
+    pub fn resolve_anchor(&self, anchor: &Anchor) -> Option<usize> {
+        if let Some(index) = anchor.id.as_deref().and_then(|id| self.find_id(id)) {
+            return Some(index.saturating_sub(anchor.before_id));
+        }
+        let start = match &anchor.label {
+            Some(l) => self.label(l)?,
+            None => 0,
+        };
+        Some((start + anchor.offset).min(self.len()))
+    }
+
+    /// Assigns generated ids to phrases and choices without explicit ones.
+    /// Generated id is a hash of section label, speakers and text, prefixed with the label.
+    pub(crate) fn assign_ids(&mut self) -> Result<(), ParseError> {
+        let mut ids = HashMap::new();
+        for (index, meta) in self.meta.iter().enumerate() {
+            if let Some(id) = &meta.id
+                && ids.insert(id.clone(), index).is_some()
+            {
+                return Err(ParseError::DuplicateId(id.clone()));
+            }
+        }
+        for index in 0..self.len() {
+            if self.meta[index].id.is_some() {
+                continue;
+            }
+            let mut hasher = StableHasher::new();
+            let label = self.section(index).unwrap_or("");
+            hasher.write_str(label);
+            match &self[index] {
+                DialogueEntry::Phrase(h, t) => {
+                    hasher.write(&[0]);
+                    h.iter().for_each(|h| hasher.write_str(h));
+                    hasher.write_str(t);
+                }
+                DialogueEntry::Choice(c) => {
+                    hasher.write(&[1]);
+                    c.iter().for_each(|c| hasher.write_str(&c.text));
+                }
+                _ => continue,
+            }
+            let hash = hasher.finish() as u32;
+            let base = if label.is_empty() {
+                format!("{:08x}", hash)
+            } else {
+                format!("{}-{:08x}", label, hash)
+            };
+            // Identical lines in the same section are numbered in order.
+            let mut id = base.clone();
+            let mut n = 1;
+            while ids.contains_key(&id) {
+                n += 1;
+                id = format!("{}-{}", base, n);
+            }
+            ids.insert(id.clone(), index);
+            self.meta[index].id = Some(id);
+        }
+        self.ids = ids;
+        Ok(())
+    }
+}
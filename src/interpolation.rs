@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use pest::Parser;
+
+use crate::{
+    DialasmParser, Dialogue, Expression, FunctionRegistry, ParseError, Rule, Span, Value,
+    parse_markup,
+};
+
+/// Part of phrase or choice text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextPart {
+    /// Text shown as is, with escaped braces (`{{` and `}}`) replaced by single ones.
+    Text(String),
+    /// Expression evaluated at display time. Example: {$gold}
+    Expression(Expression),
+    /// Current name of the speaker. Example: {@m}
+    Speaker(String),
+}
+
+/// Splits text into plain parts and interpolations in braces.
+pub(crate) fn parse_text(text: &str) -> Result<Vec<TextPart>, ParseError> {
+    let invalid = || ParseError::InvalidInterpolation(text.to_string());
+    let mut parts = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find(['{', '}']) {
+        plain.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+        if let Some(r) = rest.strip_prefix(brace) {
+            plain.push_str(brace);
+            rest = r;
+            continue;
+        }
+        if brace == "}" {
+            return Err(invalid());
+        }
+        let end = rest.find('}').ok_or_else(invalid)?;
+        // Quotes inside the string literal are escaped, but not inside expressions.
+        let source = rest[..end].replace("\\\"", "\"").replace("\\\\", "\\");
+        rest = &rest[end + 1..];
+        let pair = DialasmParser::parse(Rule::interpolation, &source)
+            .map_err(|_| invalid())?
+            .peek()
+            .unwrap()
+            .into_inner()
+            .peek()
+            .unwrap();
+        if !plain.is_empty() {
+            parts.push(TextPart::Text(std::mem::take(&mut plain)));
+        }
+        parts.push(if pair.as_rule() == Rule::handle {
+            TextPart::Speaker(pair.into_inner().peek().unwrap().as_str().to_string())
+        } else {
+            TextPart::Expression(Expression::from_pair(pair))
+        });
+    }
+    plain.push_str(rest);
+    if !plain.is_empty() {
+        parts.push(TextPart::Text(plain));
+    }
+    Ok(parts)
+}
+
+/// Splits text into parts and checks its markup.
+/// Speakers inserted into text should be known, as checked by `is_known`.
+pub(crate) fn parse_text_checked(
+    text: &str,
+    is_known: impl Fn(&str) -> bool,
+) -> Result<Vec<TextPart>, ParseError> {
+    let parts = parse_text(text)?;
+    let mut plain = String::new();
+    for part in &parts {
+        match part {
+            TextPart::Text(t) => plain.push_str(t),
+            TextPart::Speaker(h) if !is_known(h) => {
+                return Err(ParseError::UndefinedSpeaker(h.clone()));
+            }
+            _ => (),
+        }
+    }
+    // Interpolated values are escaped, so only the written text can have tags.
+    parse_markup(&plain).map_err(|e| ParseError::InvalidMarkup(text.to_string(), e))?;
+    Ok(parts)
+}
+
+/// Joins parts of text, evaluating interpolations.
+/// Expressions which fail to evaluate are shown as written, so they are easy to notice.
+/// Brackets in interpolated values are escaped, so they aren't parsed as markup.
+pub(crate) fn render_text(
+    parts: &[TextPart],
+    name: impl Fn(&str) -> String,
+    variables: &HashMap<String, Value>,
+    functions: &FunctionRegistry<'_>,
+) -> String {
+    parts
+        .iter()
+        .map(|p| match p {
+            TextPart::Text(t) => t.clone(),
+            TextPart::Expression(e) => match e.evaluate(variables, functions) {
+                Ok(v) => v.to_string().replace('[', "[["),
+                Err(_) => format!("{{{}}}", e).replace('[', "[["),
+            },
+            TextPart::Speaker(h) => name(h).replace('[', "[["),
+        })
+        .collect()
+}
+
+/// Markup spans of text as written, with interpolations kept in braces.
+/// Markup was checked while parsing, so text which fails to parse is returned as a single span.
+pub(crate) fn written_spans(parts: &[TextPart]) -> Vec<Span> {
+    let text: String = parts
+        .iter()
+        .map(|p| match p {
+            TextPart::Text(t) => t.clone(),
+            TextPart::Expression(e) => format!("{{{}}}", e).replace('[', "[["),
+            TextPart::Speaker(h) => format!("{{@{}}}", h),
+        })
+        .collect();
+    parse_markup(&text).unwrap_or_else(|_| {
+        vec![Span::Text {
+            text,
+            tags: Vec::new(),
+        }]
+    })
+}
+
+/// Joins parts of text for documents, such as scripts for actors or reviewers.
+/// Variables are unknown there, so expressions are shown as written.
+/// Quotes escaped in the script are unescaped.
+pub(crate) fn document_text(parts: &[TextPart], name: impl Fn(&str) -> String) -> String {
+    render_text(parts, name, &HashMap::new(), &FunctionRegistry::new()).replace("\\\"", "\"")
+}
+
+impl Dialogue {
+    /// Text of phrase or choice option as it's shown in documents, with current speaker names.
+    /// Markup is kept.
+    pub(crate) fn document_entry_text(
+        &self,
+        index: usize,
+        option: usize,
+        source: &str,
+        names: &HashMap<&str, &str>,
+    ) -> String {
+        match self.meta(index).and_then(|m| m.texts.get(option)) {
+            Some(parts) => document_text(parts, |h| {
+                names.get(h).map_or(format!("@{}", h), |n| n.to_string())
+            }),
+            None => source.replace("\\\"", "\""),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Dialogue, DialogueEntry, TextPart, hash::StableHasher, interpolation::parse_text_checked,
+};
+
+/// Hash of source text. Translations store hash of the text they were translated from,
+/// to notice when it changes.
+pub fn source_hash(text: &str) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.write_str(text);
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+/// What the translatable string is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringKind {
+    Phrase,
+    Choice,
+    Name,
+}
+
+impl StringKind {
+    /// Kind of string by its key, for formats which don't store it.
+    pub(crate) fn of_key(key: &str) -> StringKind {
+        if key.starts_with('@') {
+            StringKind::Name
+        } else if key.contains('.') {
+            StringKind::Choice
+        } else {
+            StringKind::Phrase
+        }
+    }
+}
+
+/// Translatable string of the dialogue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StringEntry {
+    /// Stable key of the string. Phrase id for phrases, `id.option` for choice options
+    /// (options are counted from 0), and `@handle` for speaker names.
+    /// Other names assigned to the same speaker are keyed `@handle-2`, `@handle-3` and so on.
+    pub key: String,
+    pub kind: StringKind,
+    pub text: String,
+    /// Handles of phrase speakers, or handle of the named speaker.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speakers: Vec<String>,
+    /// Label of the section the string is in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Line of the statement in the source, starting from 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// `source_hash()` of the source text. In translated tables it's the hash of the text
+    /// the string was translated from, so changes of the source can be noticed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+}
+
+/// Differences between strings of the script and their translation.
+/// Created with `Dialogue::translation_status()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TranslationStatus {
+    pub language: Option<String>,
+    /// Keys of strings which are not translated.
+    pub new: Vec<String>,
+    /// Keys of strings which have changed since they were translated.
+    pub changed: Vec<String>,
+    /// Keys of translated strings which don't exist in the script anymore.
+    pub removed: Vec<String>,
+}
+
+impl TranslationStatus {
+    /// Whether translation is complete and up to date.
+    pub fn is_up_to_date(&self) -> bool {
+        self.new.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Every translatable string of a dialogue, or their translations.
+/// Created with `Dialogue::strings()`, serialized to give it to translators.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StringTable {
+    /// Language of texts, `None` for the source language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub entries: Vec<StringEntry>,
+}
+
+impl StringTable {
+    /// Entry with the key. Looks through every entry, so build a map to look up many keys.
+    pub fn get(&self, key: &str) -> Option<&StringEntry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+
+    /// Text of the entry with the key.
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.get(key).map(|e| e.text.as_str())
+    }
+
+    /// Copy of the source table with texts replaced by translated ones.
+    /// Strings missing from `translation` keep the source text.
+    pub(crate) fn translated(&self, translation: &StringTable) -> StringTable {
+        let translated: HashMap<&str, &StringEntry> = translation
+            .entries
+            .iter()
+            .map(|e| (e.key.as_str(), e))
+            .collect();
+        let mut table = self.clone();
+        table.language = translation.language.clone();
+        for entry in &mut table.entries {
+            if let Some(t) = translated.get(entry.key.as_str()) {
+                entry.text = t.text.clone();
+                if t.source_hash.is_some() {
+                    entry.source_hash = t.source_hash.clone();
+                }
+            }
+        }
+        table
+    }
+
+    /// Compares translated table with the source one.
+    /// Strings without source hash are considered to be translated from the current text.
+    pub fn status(&self, source: &StringTable) -> TranslationStatus {
+        let translated: HashMap<&str, &StringEntry> =
+            self.entries.iter().map(|e| (e.key.as_str(), e)).collect();
+        let mut status = TranslationStatus {
+            language: self.language.clone(),
+            ..TranslationStatus::default()
+        };
+        for entry in &source.entries {
+            match translated.get(entry.key.as_str()) {
+                None => status.new.push(entry.key.clone()),
+                Some(t) => {
+                    if t.source_hash
+                        .as_ref()
+                        .is_some_and(|h| *h != source_hash(&entry.text))
+                    {
+                        status.changed.push(entry.key.clone());
+                    }
+                }
+            }
+        }
+        let keys: HashSet<&str> = source.entries.iter().map(|e| e.key.as_str()).collect();
+        status.removed = self
+            .entries
+            .iter()
+            .filter(|e| !keys.contains(e.key.as_str()))
+            .map(|e| e.key.clone())
+            .collect();
+        status
+    }
+}
+
+impl Dialogue {
+    /// Key of phrase text, or text of choice option, in string table.
+    pub fn text_key(&self, index: usize, option: usize) -> Option<String> {
+        let id = self.id(index)?;
+        match self.get(index)? {
+            DialogueEntry::Phrase(..) => Some(id.to_string()),
+            DialogueEntry::Choice(c) if option < c.len() => Some(format!("{}.{}", id, option)),
+            _ => None,
+        }
+    }
+
+    /// Compares translated table with strings of the dialogue.
+    pub fn translation_status(&self, translation: &StringTable) -> TranslationStatus {
+        translation.status(&self.strings())
+    }
+
+    /// Collects phrases, choice options and speaker names, in order of appearance.
+    /// Each name is collected once, no matter how many times it's assigned.
+    pub fn strings(&self) -> StringTable {
+        let mut entries = Vec::new();
+        let mut names: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (index, entry) in self.entries().iter().enumerate() {
+            let label = self.section(index).map(str::to_string);
+            let line = self.meta(index).and_then(|m| m.line);
+            let mut push = |key: String, kind, text: &str, speakers: Vec<String>| {
+                entries.push(StringEntry {
+                    key,
+                    kind,
+                    text: text.to_string(),
+                    speakers,
+                    label: label.clone(),
+                    line,
+                    source_hash: Some(source_hash(text)),
+                })
+            };
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    let known = names.entry(h).or_default();
+                    if known.contains(&n.as_str()) {
+                        continue;
+                    }
+                    known.push(n);
+                    let key = match known.len() {
+                        1 => format!("@{}", h),
+                        n => format!("@{}-{}", h, n),
+                    };
+                    push(key, StringKind::Name, n, vec![h.clone()]);
+                }
+                DialogueEntry::Phrase(h, t) => {
+                    if let Some(key) = self.text_key(index, 0) {
+                        push(key, StringKind::Phrase, t, h.clone());
+                    }
+                }
+                DialogueEntry::Choice(c) => {
+                    for (option, choice) in c.iter().enumerate() {
+                        if let Some(key) = self.text_key(index, option) {
+                            push(key, StringKind::Choice, &choice.text, Vec::new());
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        StringTable {
+            language: None,
+            entries,
+        }
+    }
+}
+
+/// Translated texts prepared for the runner.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Translation {
+    /// Parsed texts by their keys.
+    texts: HashMap<String, Vec<TextPart>>,
+    /// Translated names by handle and source name.
+    names: HashMap<(String, String), String>,
+}
+
+impl Translation {
+    /// Matches translated table with strings of the dialogue.
+    /// Texts which fail to parse are left untranslated.
+    pub(crate) fn new(dialogue: &Dialogue, table: &StringTable) -> Translation {
+        let strings = dialogue.strings();
+        let handles: HashSet<String> = strings
+            .entries
+            .iter()
+            .filter(|e| e.kind == StringKind::Name)
+            .map(|e| e.speakers[0].clone())
+            .collect();
+        // Like `StringTable::text()`, the first entry with the key is used.
+        let mut translated: HashMap<&str, &str> = HashMap::new();
+        for entry in &table.entries {
+            translated.entry(&entry.key).or_insert(&entry.text);
+        }
+        let mut translation = Translation::default();
+        for source in strings.entries {
+            let Some(text) = translated.get(source.key.as_str()).copied() else {
+                continue;
+            };
+            match source.kind {
+                StringKind::Name => {
+                    translation
+                        .names
+                        .insert((source.speakers[0].clone(), source.text), text.to_string());
+                }
+                StringKind::Phrase | StringKind::Choice => {
+                    if let Ok(parts) = parse_text_checked(text, |h| handles.contains(h)) {
+                        translation.texts.insert(source.key, parts);
+                    }
+                }
+            }
+        }
+        translation
+    }
+
+    pub(crate) fn text(&self, key: &str) -> Option<&[TextPart]> {
+        self.texts.get(key).map(Vec::as_slice)
+    }
+
+    pub(crate) fn name(&self, handle: &str, name: &str) -> Option<&str> {
+        self.names
+            .get(&(handle.to_string(), name.to_string()))
+            .map(String::as_str)
+    }
+}
@@ -1,12 +1,62 @@
 use std::{
-    collections::{HashMap, HashSet},
-    ops::Index,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::{Index, Range},
 };
 
 use pest::{Parser, error::Error, iterators::Pair};
 use pest_derive::Parser;
 use thiserror::Error;
 
+mod command;
+mod csv;
+mod expression;
+mod fountain;
+mod gettext;
+mod hash;
+mod html;
+mod ids;
+mod interpolation;
+mod l10n;
+mod lint;
+mod markdown;
+mod markup;
+mod paths;
+pub mod protocol;
+mod runner;
+mod seen;
+mod speaker;
+mod stats;
+mod transcript;
+mod value;
+mod voice;
+mod xliff;
+
+pub use command::CommandHandler;
+pub use csv::{CSV_COLUMNS, CsvError};
+pub use expression::{Expression, ExpressionError, FunctionRegistry};
+pub use gettext::PoError;
+pub use ids::Anchor;
+pub use interpolation::TextPart;
+pub use l10n::{StringEntry, StringKind, StringTable, TranslationStatus, source_hash};
+pub use lint::{LintIssue, LintOptions};
+pub use markup::{MarkupError, Span, Tag, VOID_TAGS, parse_markup, plain_text};
+pub use paths::{Coverage, CoverageError, DialoguePath, count_endings};
+pub use runner::{
+    Choices, DEFAULT_HISTORY_LIMIT, Event, HistoryEntry, Line, RunError, Runner, RunnerState,
+};
+pub use seen::SeenSet;
+pub use speaker::Speaker;
+pub use stats::{DialogueStats, SpeakerStats, WORDS_PER_MINUTE, word_count};
+pub use transcript::{
+    TRANSCRIPT_SEPARATOR, TranscriptError, TranscriptMismatch, TranscriptTest, compare_transcripts,
+    transcript,
+};
+pub use value::{Value, ValueType};
+pub use voice::{
+    AUDIO_EXTENSIONS, AudioReport, VO_MANIFEST_COLUMNS, VoActor, VoLine, VoScript, vo_file,
+};
+pub use xliff::{XliffError, XliffReport};
+
 #[derive(Parser)]
 #[grammar = "dialasm.pest"]
 pub struct DialasmParser;
@@ -21,6 +71,90 @@ pub enum ParseError {
     UndefinedLabel(String),
     #[error("Duplicate label '{0}'")]
     DuplicateLabel(String),
+    #[error("Duplicate id '{0}'")]
+    DuplicateId(String),
+    #[error("Duplicate tag '{0}'")]
+    DuplicateTag(String),
+    #[error("Duplicate attribute '{0}'")]
+    DuplicateAttribute(String),
+    #[error("Unknown command '{0}'")]
+    UnknownCommand(String),
+    #[error("Command '{name}' expects {expected} arguments, got {found}")]
+    CommandArity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Argument {index} of command '{name}' should be {expected}")]
+    CommandArgumentType {
+        name: String,
+        index: usize,
+        expected: ValueType,
+    },
+    #[error("Invalid interpolation in \"{0}\"")]
+    InvalidInterpolation(String),
+    #[error("Invalid markup in \"{0}\": {1}")]
+    InvalidMarkup(String, MarkupError),
+    #[error("Invalid expression: {0}")]
+    InvalidExpression(ExpressionError),
+}
+
+/// Parsing options. Created with `ParseOptions::new()` and configured with builder methods.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions<'a> {
+    commands: Option<HashMap<String, Vec<ValueType>>>,
+    functions: Option<&'a FunctionRegistry<'a>>,
+}
+
+impl<'a> ParseOptions<'a> {
+    pub fn new() -> ParseOptions<'a> {
+        ParseOptions::default()
+    }
+
+    /// Registers command with types of its parameters. Once any command is registered,
+    /// unknown commands and commands with wrong arguments fail to parse.
+    pub fn command(mut self, name: &str, parameters: &[ValueType]) -> ParseOptions<'a> {
+        self.commands
+            .get_or_insert_with(HashMap::new)
+            .insert(name.to_string(), parameters.to_vec());
+        self
+    }
+
+    /// Sets functions of the host. Expressions are checked against their signatures
+    /// with `Dialogue::validate_functions()`, and the dialogue fails to parse if they don't fit.
+    pub fn functions(mut self, functions: &'a FunctionRegistry<'a>) -> ParseOptions<'a> {
+        self.functions = Some(functions);
+        self
+    }
+
+    /// Checks command against registered signature.
+    fn check_command(&self, name: &str, arguments: &[Value]) -> Result<(), ParseError> {
+        let Some(commands) = &self.commands else {
+            return Ok(());
+        };
+        let parameters = commands
+            .get(name)
+            .ok_or_else(|| ParseError::UnknownCommand(name.to_string()))?;
+        if parameters.len() != arguments.len() {
+            return Err(ParseError::CommandArity {
+                name: name.to_string(),
+                expected: parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        match parameters
+            .iter()
+            .zip(arguments)
+            .position(|(p, a)| *p != a.value_type())
+        {
+            Some(index) => Err(ParseError::CommandArgumentType {
+                name: name.to_string(),
+                index,
+                expected: parameters[index],
+            }),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -35,12 +169,40 @@ pub enum DialogueEntry {
     Phrase(Vec<String>, String),
     Choice(Vec<DialogueChoice>),
     Jump(String),
+    Command(String, Vec<Value>),
+    /// Jumps to label if the condition is true.
+    ConditionalJump(String, Expression),
+    /// Sets attributes of the speaker, keeping the ones not mentioned.
+    AttributeChange(String, BTreeMap<String, Value>),
+}
+
+/// Data attached to an entry which doesn't affect execution.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct EntryMeta {
+    /// Stable id of phrase or choice, `None` for other entries.
+    /// Either set in the source with `#id:name`, or generated.
+    pub id: Option<String>,
+    /// Whether `id` is set in the source. Generated ids change when the text is edited.
+    pub explicit_id: bool,
+    /// Line of the statement in the source, starting from 1. `None` if not parsed from source.
+    pub line: Option<usize>,
+    /// Metadata tags of phrase or choice, set in the source with `#key:value`.
+    pub tags: BTreeMap<String, String>,
+    /// Parsed text of phrase, or texts of every choice option. Empty for other entries.
+    pub texts: Vec<Vec<TextPart>>,
+    /// Byte ranges of texts in the source, without quotes. Parallel to `texts`.
+    pub ranges: Vec<Range<usize>>,
+    /// Texts split by markup, with interpolations kept in braces as written.
+    /// Parallel to `texts`.
+    pub spans: Vec<Vec<Span>>,
 }
 
 #[derive(Debug)]
 pub struct Dialogue {
     entries: Vec<DialogueEntry>,
     labels: HashMap<String, usize>,
+    meta: Vec<EntryMeta>,
+    ids: HashMap<String, usize>,
 }
 
 impl Dialogue {
@@ -101,15 +263,39 @@ impl Dialogue {
             (String::from("last"), 11),
             (String::from("end"), 13),
         ]);
-        Dialogue { entries, labels }
+        Dialogue::new(entries, labels, Vec::new()).unwrap()
+    }
+
+    /// Creates dialogue and assigns ids to entries without explicit ones.
+    fn new(
+        entries: Vec<DialogueEntry>,
+        labels: HashMap<String, usize>,
+        mut meta: Vec<EntryMeta>,
+    ) -> Result<Dialogue, ParseError> {
+        meta.resize_with(entries.len(), EntryMeta::default);
+        let mut dialogue = Dialogue {
+            entries,
+            labels,
+            meta,
+            ids: HashMap::new(),
+        };
+        dialogue.assign_ids()?;
+        Ok(dialogue)
     }
 
     /// Parses source. If there is an error then it fails.
     pub fn parse(src: &str) -> Result<Dialogue, ParseError> {
-        let program = DialasmParser::parse(Rule::program, src).map_err(|e| ParseError::InvalidProgram(Box::new(e)))?;
+        Self::parse_with(src, &ParseOptions::default())
+    }
+
+    /// Parses source with options. If there is an error then it fails.
+    pub fn parse_with(src: &str, options: &ParseOptions) -> Result<Dialogue, ParseError> {
+        let program = DialasmParser::parse(Rule::program, src)
+            .map_err(|e| ParseError::InvalidProgram(Box::new(e)))?;
         let mut program_inner = program.peek().unwrap().into_inner();
         let mut labels = HashMap::new();
         let mut entries = Vec::new();
+        let mut meta = Vec::new();
         let mut unknown_labels: HashSet<String> = HashSet::new();
         let mut known_speakers: HashSet<String> = HashSet::new();
         program_inner.try_fold(0, |idx, p| {
@@ -126,13 +312,54 @@ impl Dialogue {
                 return Ok(idx);
             };
             let statement = statement.into_inner().peek().unwrap();
+            let id = Self::parse_id_tag(&statement)?;
+            meta.push(EntryMeta {
+                explicit_id: id.is_some(),
+                id,
+                line: Some(statement.line_col().0),
+                tags: Self::parse_meta_tags(&statement)?,
+                texts: Vec::new(),
+                ranges: Self::text_ranges(&statement),
+                spans: Vec::new(),
+            });
             match statement.as_rule() {
                 Rule::name_statement => {
+                    let attributes = statement
+                        .clone()
+                        .into_inner()
+                        .find(|p| p.as_rule() == Rule::attributes);
                     let result = Self::parse_name_statement(statement);
                     if let DialogueEntry::NameChange(n, _) = &result {
                         known_speakers.insert(n.clone());
                     };
-                    entries.push(result);
+                    // Declaration with attributes is a name change followed by attribute change.
+                    if let (Some(attributes), DialogueEntry::NameChange(n, _)) =
+                        (attributes, &result)
+                    {
+                        let change = DialogueEntry::AttributeChange(
+                            n.clone(),
+                            Self::parse_attributes(attributes)?,
+                        );
+                        entries.push(result);
+                        entries.push(change);
+                        meta.push(EntryMeta {
+                            line: meta.last().unwrap().line,
+                            ..EntryMeta::default()
+                        });
+                    } else {
+                        entries.push(result);
+                    }
+                }
+                Rule::attribute_statement => {
+                    let mut inner = statement.into_inner();
+                    let handle = inner.next().unwrap().into_inner().peek().unwrap().as_str();
+                    if !known_speakers.contains(handle) {
+                        return Err(ParseError::UndefinedSpeaker(handle.to_string()));
+                    }
+                    entries.push(DialogueEntry::AttributeChange(
+                        handle.to_string(),
+                        Self::parse_attributes(inner.next().unwrap())?,
+                    ));
                 }
                 Rule::phrase_statement => {
                     let result = Self::parse_phrase_statement(statement);
@@ -144,6 +371,7 @@ impl Dialogue {
                             Ok(())
                         })?;
                     };
+                    Self::set_texts(meta.last_mut().unwrap(), &result, &known_speakers)?;
                     entries.push(result);
                 }
                 Rule::choice_statement => {
@@ -155,27 +383,124 @@ impl Dialogue {
                             }
                         });
                     }
+                    Self::set_texts(meta.last_mut().unwrap(), &result, &known_speakers)?;
                     entries.push(result);
                 }
                 Rule::jump_statement => {
                     let result = Self::parse_jump_statement(statement);
-                    if let DialogueEntry::Jump(n) = &result
+                    if let DialogueEntry::Jump(n) | DialogueEntry::ConditionalJump(n, _) = &result
                         && !labels.contains_key(n)
                     {
                         unknown_labels.insert(n.clone());
                     }
                     entries.push(result);
                 }
+                Rule::command_statement => {
+                    let result = Self::parse_command_statement(statement);
+                    if let DialogueEntry::Command(n, a) = &result {
+                        options.check_command(n, a)?;
+                    }
+                    entries.push(result);
+                }
                 _ => (),
             };
-            Ok(idx + 1)
+            Ok(entries.len())
         })?;
         if !unknown_labels.is_empty() {
             return Err(ParseError::UndefinedLabel(
                 unknown_labels.iter().next().unwrap().clone(),
             ));
         }
-        Ok(Dialogue { entries, labels })
+        let dialogue = Dialogue::new(entries, labels, meta)?;
+        if let Some(functions) = options.functions {
+            dialogue
+                .validate_functions(functions)
+                .map_err(ParseError::InvalidExpression)?;
+        }
+        Ok(dialogue)
+    }
+
+    fn parse_id_tag(pair: &Pair<'_, Rule>) -> Result<Option<String>, ParseError> {
+        let mut ids = pair
+            .clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::id_tag)
+            .map(|p| p.into_inner().peek().unwrap().as_str().to_string());
+        let id = ids.next();
+        if ids.next().is_some() {
+            return Err(ParseError::DuplicateTag(String::from("id")));
+        }
+        Ok(id)
+    }
+
+    /// Byte ranges of phrase or choice texts in the source. Empty for other statements.
+    fn text_ranges(pair: &Pair<'_, Rule>) -> Vec<Range<usize>> {
+        if !matches!(
+            pair.as_rule(),
+            Rule::phrase_statement | Rule::choice_statement
+        ) {
+            return Vec::new();
+        }
+        pair.clone()
+            .into_inner()
+            .flatten()
+            .filter(|p| p.as_rule() == Rule::string_content)
+            .map(|p| p.as_span().start()..p.as_span().end())
+            .collect()
+    }
+
+    /// Parses texts of phrase or choice into metadata of the entry, checking interpolations and
+    /// markup. Speakers inserted into text should be defined, same as speakers of phrases.
+    fn set_texts(
+        meta: &mut EntryMeta,
+        entry: &DialogueEntry,
+        known_speakers: &HashSet<String>,
+    ) -> Result<(), ParseError> {
+        let texts = match entry {
+            DialogueEntry::Phrase(_, t) => vec![t.as_str()],
+            DialogueEntry::Choice(c) => c.iter().map(|c| c.text.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        meta.texts = texts
+            .into_iter()
+            .map(|t| interpolation::parse_text_checked(t, |h| known_speakers.contains(h)))
+            .collect::<Result<_, _>>()?;
+        meta.spans = meta
+            .texts
+            .iter()
+            .map(|t| interpolation::written_spans(t))
+            .collect();
+        Ok(())
+    }
+
+    fn parse_meta_tags(pair: &Pair<'_, Rule>) -> Result<BTreeMap<String, String>, ParseError> {
+        let mut tags = BTreeMap::new();
+        for tag in pair
+            .clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::meta_tag)
+        {
+            let mut inner = tag.into_inner();
+            let key = inner.next().unwrap().as_str().to_string();
+            let value = inner.next().unwrap().as_str().to_string();
+            if tags.insert(key.clone(), value).is_some() {
+                return Err(ParseError::DuplicateTag(key));
+            }
+        }
+        Ok(tags)
+    }
+
+    fn parse_attributes(pair: Pair<'_, Rule>) -> Result<BTreeMap<String, Value>, ParseError> {
+        let mut attributes = BTreeMap::new();
+        for attribute in pair.into_inner() {
+            let mut inner = attribute.into_inner();
+            let key = inner.next().unwrap().as_str().to_string();
+            let value = Value::from_literal(inner.next().unwrap());
+            if attributes.insert(key.clone(), value).is_some() {
+                return Err(ParseError::DuplicateAttribute(key));
+            }
+        }
+        Ok(attributes)
     }
 
     fn parse_name_statement(pair: Pair<'_, Rule>) -> DialogueEntry {
@@ -261,7 +586,26 @@ impl Dialogue {
     }
 
     fn parse_jump_statement(pair: Pair<'_, Rule>) -> DialogueEntry {
-        DialogueEntry::Jump(pair.into_inner().peek().unwrap().as_str().to_string())
+        let mut inner = pair.into_inner();
+        let label = inner.next().unwrap().as_str().to_string();
+        match inner.next() {
+            Some(condition) => {
+                DialogueEntry::ConditionalJump(label, Expression::from_pair(condition))
+            }
+            None => DialogueEntry::Jump(label),
+        }
+    }
+
+    fn parse_command_statement(pair: Pair<'_, Rule>) -> DialogueEntry {
+        let mut inner = pair.into_inner();
+        let name = inner.next().unwrap().as_str().to_string();
+        let arguments = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(Value::from_literal)
+            .collect();
+        DialogueEntry::Command(name, arguments)
     }
 
     /// Safely get dialogue entry (instruction).
@@ -279,6 +623,26 @@ impl Dialogue {
         &self.entries
     }
 
+    /// Returns data attached to the entry.
+    pub fn meta(&self, index: usize) -> Option<&EntryMeta> {
+        self.meta.get(index)
+    }
+
+    /// Metadata tags of phrase or choice. Empty for other entries.
+    pub fn tags(&self, index: usize) -> Option<&BTreeMap<String, String>> {
+        self.meta.get(index).map(|m| &m.tags)
+    }
+
+    /// Value of metadata tag of phrase or choice.
+    pub fn tag(&self, index: usize, key: &str) -> Option<&str> {
+        self.tags(index)?.get(key).map(String::as_str)
+    }
+
+    /// Indices of entries which have the tag, with its values.
+    pub fn tagged<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        (0..self.len()).filter_map(move |i| self.tag(i, key).map(|v| (i, v)))
+    }
+
     /// Returns immutable map of labels to their pointers.
     pub fn labels(&self) -> &HashMap<String, usize> {
         &self.labels
@@ -310,6 +674,6 @@ impl Index<usize> for Dialogue {
 
 impl PartialEq<Dialogue> for Dialogue {
     fn eq(&self, other: &Dialogue) -> bool {
-        self.entries == other.entries && self.labels == other.labels
+        self.entries == other.entries && self.labels == other.labels && self.meta == other.meta
     }
 }
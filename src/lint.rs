@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::{Dialogue, DialogueEntry};
+
+/// Problem found by `Dialogue::lint()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintIssue {
+    /// Name of the rule which found the problem, such as "unreachable".
+    pub rule: &'static str,
+    pub message: String,
+    /// Line of the statement in the source, starting from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// Lint options. Created with `LintOptions::new()` and configured with builder methods.
+/// Every rule has to be enabled.
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    unreachable: bool,
+    audio: Option<(Vec<String>, String)>,
+}
+
+impl LintOptions {
+    pub fn new() -> LintOptions {
+        LintOptions::default()
+    }
+
+    /// Enables rule "unreachable", which reports statements found by `Dialogue::unreachable()`.
+    pub fn unreachable(mut self) -> LintOptions {
+        self.unreachable = true;
+        self
+    }
+
+    /// Enables rule "audio", which checks voice-over recordings with `VoScript::check_audio()`.
+    /// `files` are paths relative to the audio directory, `extension` is the one expected
+    /// in the voice-over script.
+    pub fn audio(mut self, files: &[String], extension: &str) -> LintOptions {
+        self.audio = Some((files.to_vec(), extension.to_string()));
+        self
+    }
+}
+
+impl Dialogue {
+    /// Indices of entries the dialogue never gets to, neither from the start nor from any label.
+    pub fn unreachable(&self) -> BTreeSet<usize> {
+        let mut unvisited: BTreeSet<usize> = (0..self.len()).collect();
+        let mut stack: Vec<usize> = self.labels().values().copied().collect();
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            if !unvisited.remove(&index) {
+                continue;
+            }
+            match &self[index] {
+                DialogueEntry::Jump(l) => stack.extend(self.label(l)),
+                DialogueEntry::ConditionalJump(l, _) => {
+                    stack.extend(self.label(l));
+                    stack.push(index + 1);
+                }
+                DialogueEntry::Choice(c) => {
+                    stack.extend(c.iter().filter_map(|c| self.label(&c.label)))
+                }
+                _ => stack.push(index + 1),
+            }
+        }
+        unvisited
+    }
+
+    /// Checks the dialogue for problems which don't prevent it from running.
+    /// Issues are sorted by line.
+    pub fn lint(&self, options: &LintOptions) -> Vec<LintIssue> {
+        let line = |index: usize| self.meta(index).and_then(|m| m.line);
+        let mut issues = Vec::new();
+        let unreachable = if options.unreachable {
+            self.unreachable()
+        } else {
+            BTreeSet::new()
+        };
+        // Consecutive unreachable statements are reported once.
+        for index in unreachable
+            .iter()
+            .filter(|i| **i == 0 || !unreachable.contains(&(*i - 1)))
+        {
+            let count = (*index..).take_while(|i| unreachable.contains(i)).count();
+            issues.push(LintIssue {
+                rule: "unreachable",
+                message: match count {
+                    1 => String::from("Statement is never reached"),
+                    n => format!("{} statements are never reached", n),
+                },
+                line: line(*index),
+            });
+        }
+        if let Some((files, extension)) = &options.audio {
+            let script = self.vo_script(extension);
+            let report = script.check_audio(files);
+            let phrase_line = |file: &str| script.lines().find(|l| l.file == file)?.line;
+            for file in &report.missing {
+                issues.push(LintIssue {
+                    rule: "audio",
+                    message: format!("Recording '{}' is missing", file),
+                    line: phrase_line(file),
+                });
+            }
+            for (file, recordings) in &report.duplicates {
+                issues.push(LintIssue {
+                    rule: "audio",
+                    message: format!(
+                        "Recording '{}' has several files: {}",
+                        file,
+                        recordings.join(", ")
+                    ),
+                    line: phrase_line(file),
+                });
+            }
+            for file in &report.orphaned {
+                issues.push(LintIssue {
+                    rule: "audio",
+                    message: format!("Recording '{}' doesn't belong to any phrase", file),
+                    line: None,
+                });
+            }
+        }
+        issues.sort_by_key(|i| i.line.unwrap_or(usize::MAX));
+        issues
+    }
+}
@@ -1,79 +1,546 @@
 use anyhow::Result;
 use anyhow::*;
 use std::{
-    collections::HashMap,
+    collections::HashSet,
     env,
     fs::{self},
     io::{self, Write},
+    path::{Path, PathBuf},
 };
 
-use dialasm::Dialogue;
+use dialasm::{Dialogue, Event, LintOptions, Runner, StringTable, TranscriptTest, protocol};
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!(
-            "dialasm {}\nBy {}\n\nUsage:\ndialasm (<path to your script> or \"example\" for example dialogue)",
-            env!("CARGO_PKG_VERSION"),
-            env!("CARGO_PKG_AUTHORS")
-        );
+    match args.get(1).map(String::as_str) {
+        Some("stats") if args.len() >= 3 => stats(&args[2..]),
+        Some("test") if args.len() == 3 => test(Path::new(&args[2])),
+        Some("extract") if args.len() >= 3 => extract(&args[2..]),
+        Some("export") if args.len() >= 3 => export(&args[2..]),
+        Some("import") if args.len() >= 4 => import(&args[2..]),
+        Some("lint") if args.len() >= 3 => lint(&args[2..]),
+        Some("vo-script") if args.len() >= 3 => vo_script(&args[2..]),
+        Some("l10n-status") if args.len() >= 4 => l10n_status(&args[2..]),
+        Some("serve") if args.len() == 4 && args[2] == "--stdio" => serve(&load(&args[3])?),
+        // Subcommands with wrong arguments are not paths to scripts.
+        None
+        | Some(
+            "stats" | "test" | "extract" | "export" | "import" | "lint" | "vo-script"
+            | "l10n-status" | "serve",
+        ) => {
+            usage();
+            Ok(())
+        }
+        Some(path) => play(&load(path)?, PlayOptions::parse(&args[2..])?),
+    }
+}
+
+fn usage() {
+    println!(
+        "dialasm {}\nBy {}\n\nUsage:\n\
+        dialasm (<path to your script> or \"example\" for example dialogue) [options]\n  \
+          --choices 1,2,1       pick these options instead of asking\n  \
+          --auto                don't wait after phrases\n  \
+          --start <label>       start from label\n  \
+          --transcript <path>   write transcript of the playthrough\n  \
+          --translation <path>  show texts from translated string table\n\
+        dialasm stats <path> [--json]\n\
+        dialasm extract <path> [--format json|pot|xliff] [--source-language <code>] [--output <path>]\n\
+        dialasm export <path> --csv|--tsv|--fountain|--html|--markdown [--output <path>]\n\
+        dialasm import <path> <edited .csv or .tsv file> [--output <path>]\n\
+        dialasm lint <path> [--unreachable] [--audio <directory>] [--extension <ext>] [--json]\n\
+        dialasm vo-script <path> [--output <directory>] [--extension <ext>]\n\
+        dialasm l10n-status <path> <translation files...> [--json]\n\
+        dialasm test <directory with .dlgtest files>\n\
+        dialasm serve --stdio <path>",
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_AUTHORS")
+    );
+}
+
+/// Loads dialogue from path, or example dialogue if path is "example".
+fn load(path: &str) -> Result<Dialogue> {
+    if path == "example" {
+        return Ok(Dialogue::example());
+    }
+    let contents = fs::read_to_string(path)?;
+    match Dialogue::parse(&contents) {
+        Result::Ok(parsed) => Ok(parsed),
+        Err(e) => Err(anyhow!(e)),
+    }
+}
+
+fn stats(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let stats = dlg.stats();
+    if args[1..].iter().any(|a| a == "--json") {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
         return Ok(());
     }
+    println!("{:<24} {:>8} {:>8}", "Speaker", "Lines", "Words");
+    for s in &stats.speakers {
+        let speaker = match (&s.handle, &s.name) {
+            (Some(h), Some(n)) => format!("@{} ({})", h, n),
+            (Some(h), None) => format!("@{}", h),
+            _ => String::from("(anonymous)"),
+        };
+        println!("{:<24} {:>8} {:>8}", speaker, s.lines, s.words);
+    }
+    println!();
+    println!("Lines: {}", stats.lines);
+    println!(
+        "Words: {} (+{} in choices)",
+        stats.words, stats.choice_words
+    );
+    println!(
+        "Choices: {} ({} options, branching factor {:.2})",
+        stats.choices, stats.options, stats.branching_factor
+    );
+    println!("Labels: {}", stats.labels);
+    let seconds = stats.reading_time.round() as u64;
+    println!("Reading time: {}m {:02}s", seconds / 60, seconds % 60);
+    Ok(())
+}
 
-    let path = args.get(1).unwrap();
-    let dlg: Dialogue = if path == "example" {
-        Dialogue::example()
-    } else {
-        let contents = fs::read_to_string(path)?;
-        match Dialogue::parse(&contents) {
-            Result::Ok(parsed) => parsed,
-            Err(e) => return Err(anyhow!(e)),
+/// Writes string table of the dialogue in the format, to stdout or to the file.
+fn extract(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let mut format = "json";
+    let mut source_language = "en";
+    let mut output = None;
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--format" => format = options.next().context("Missing value of --format")?,
+            "--source-language" => {
+                source_language = options
+                    .next()
+                    .context("Missing value of --source-language")?
+            }
+            "--output" => output = Some(options.next().context("Missing value of --output")?),
+            _ => bail!("Unknown option '{}'", arg),
         }
+    }
+    let table = dlg.strings();
+    let contents = match format {
+        "json" => serde_json::to_string_pretty(&table)? + "\n",
+        "pot" => table.to_pot(&args[0]),
+        "xliff" => table.to_xliff(&args[0], source_language, None),
+        _ => bail!("Unknown format '{}'", format),
     };
-    
-    let mut pointer: usize = 0;
-    let mut speakers = HashMap::new();
-    while pointer < dlg.len() {
-        match &dlg[pointer] {
-            dialasm::DialogueEntry::NameChange(h, n) => {
-                speakers.insert(h, n);
-                pointer += 1;
+    match output {
+        Some(path) => fs::write(path, contents)?,
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+/// Writes the dialogue in another format, to stdout or to the file.
+fn export(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let mut format = None;
+    let mut output = None;
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--csv" | "--tsv" | "--fountain" | "--html" | "--markdown" => {
+                format = Some(arg.as_str())
             }
-            dialasm::DialogueEntry::Phrase(h, t) => {
-                let speaker_names = h.iter().map(|x| speakers[x].clone()).collect::<Vec<String>>().join(" & ");
-                print!("{}: {}", speaker_names, t);
-                io::stdout().flush().expect("Failed to flush stdout");
-                let mut buffer = String::new();
-                io::stdin()
-                    .read_line(&mut buffer)
-                    .expect("Failed to read line from stdin");
-                pointer += 1;
+            "--output" => output = Some(options.next().context("Missing value of --output")?),
+            _ => bail!("Unknown option '{}'", arg),
+        }
+    }
+    let title = Path::new(&args[0])
+        .file_stem()
+        .map_or(String::new(), |s| s.to_string_lossy().to_string());
+    let contents = match format.context("Missing export format")? {
+        "--tsv" => dlg.to_csv('\t'),
+        "--fountain" => dlg.to_fountain(Some(&title)),
+        "--html" => dlg.to_html(&title),
+        "--markdown" => dlg.to_markdown(Some(&title)),
+        _ => dlg.to_csv(','),
+    };
+    match output {
+        Some(path) => fs::write(path, contents)?,
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+/// Applies texts edited in the spreadsheet to the script, and writes the result
+/// to stdout or to the file. Spreadsheets with `.tsv` extension are tab separated.
+fn import(args: &[String]) -> Result<()> {
+    let src = fs::read_to_string(&args[0])?;
+    let sheet_path = Path::new(&args[1]);
+    let delimiter = match sheet_path.extension().and_then(|e| e.to_str()) {
+        Some("tsv") => '\t',
+        _ => ',',
+    };
+    let mut output = None;
+    let mut options = args[2..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--output" => output = Some(options.next().context("Missing value of --output")?),
+            _ => bail!("Unknown option '{}'", arg),
+        }
+    }
+    let result = Dialogue::apply_csv(&src, &fs::read_to_string(sheet_path)?, delimiter)?;
+    match output {
+        Some(path) => fs::write(path, result)?,
+        None => print!("{}", result),
+    }
+    Ok(())
+}
+
+/// Writes recording script of every actor and the manifest of expected files to the directory.
+fn vo_script(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let mut output = "vo";
+    let mut extension = "wav";
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--output" => output = options.next().context("Missing value of --output")?,
+            "--extension" => extension = options.next().context("Missing value of --extension")?,
+            _ => bail!("Unknown option '{}'", arg),
+        }
+    }
+    let script = dlg.vo_script(extension);
+    // Group phrases are listed for every speaker, but warned about once.
+    let mut warned = HashSet::new();
+    for line in script.lines().filter(|l| !l.explicit_id) {
+        if warned.insert(&line.id) {
+            eprintln!(
+                "warning: {}:{}: phrase has no #id:, its recordings are renamed when the text changes",
+                args[0],
+                line.line.unwrap_or_default()
+            );
+        }
+    }
+    let dir = Path::new(output);
+    fs::create_dir_all(dir)?;
+    for actor in &script.actors {
+        let path = dir.join(actor.file_name());
+        fs::write(&path, actor.to_markdown())?;
+        println!("{}", path.display());
+    }
+    let manifest = dir.join("manifest.csv");
+    fs::write(&manifest, script.manifest())?;
+    println!("{}", manifest.display());
+    Ok(())
+}
+
+/// Collects paths of every file in directory and its subdirectories, relative to `root`
+/// and separated with `/`.
+fn find_files(dir: &Path, root: &Path, result: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_files(&path, root, result)?;
+        } else {
+            let relative = path.strip_prefix(root)?;
+            let parts: Vec<_> = relative.iter().map(|p| p.to_string_lossy()).collect();
+            result.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the script for problems, and recordings in the audio directory if it's given.
+fn lint(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let mut lint_options = LintOptions::new();
+    let mut audio = None;
+    let mut extension = "wav";
+    let mut json = false;
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--unreachable" => lint_options = lint_options.unreachable(),
+            "--audio" => audio = Some(options.next().context("Missing value of --audio")?),
+            "--extension" => extension = options.next().context("Missing value of --extension")?,
+            "--json" => json = true,
+            _ => bail!("Unknown option '{}'", arg),
+        }
+    }
+    if let Some(dir) = audio {
+        let mut files = Vec::new();
+        find_files(Path::new(dir), Path::new(dir), &mut files)?;
+        lint_options = lint_options.audio(&files, extension);
+    }
+    let issues = dlg.lint(&lint_options);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+    } else {
+        for issue in &issues {
+            match issue.line {
+                Some(line) => println!("{}:{}: {} [{}]", args[0], line, issue.message, issue.rule),
+                None => println!("{}: {} [{}]", args[0], issue.message, issue.rule),
             }
-            dialasm::DialogueEntry::Choice(choices) => {
-                let mut proceed = false;
-                while !proceed {
-                    for i in 1..=choices.len() {
-                        println!("{}: {}", i, choices[i - 1].text);
-                    }
-                    io::stdout().flush().expect("Failed to flush stdout");
-                    let mut buffer = String::new();
-                    io::stdin()
-                        .read_line(&mut buffer)
-                        .expect("Failed to read line from stdin");
-                    if let Result::Ok(idx) = buffer.trim_end().parse::<usize>() {
-                        if idx < 1 || idx > choices.len() {
-                            println!("Invalid choice index");
-                            continue;
-                        }
-                        proceed = true;
-                        pointer = dlg.label(&choices[idx - 1].label).unwrap();
-                    }
+        }
+    }
+    if !issues.is_empty() {
+        bail!("{} problem(s) found", issues.len());
+    }
+    Ok(())
+}
+
+/// Loads translated string table from JSON, gettext or XLIFF file.
+/// Problems found in XLIFF files are reported to stderr.
+fn load_translation(dlg: &Dialogue, path: &Path) -> Result<StringTable> {
+    let contents = fs::read_to_string(path)?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("po") => Ok(StringTable::from_po(&contents, &dlg.strings())?),
+        Some("xlf" | "xliff") => {
+            let (table, report) = StringTable::from_xliff(&contents, &dlg.strings())?;
+            for id in &report.unknown {
+                eprintln!("warning: unit '{}' doesn't exist in the script", id);
+            }
+            for id in &report.stale {
+                eprintln!("warning: unit '{}' is translated from older text", id);
+            }
+            for id in &report.missing {
+                eprintln!("warning: unit '{}' is not translated", id);
+            }
+            Ok(table)
+        }
+        _ => Ok(serde_json::from_str(&contents)?),
+    }
+}
+
+/// Reads translated strings from JSON, gettext or XLIFF file, without matching them
+/// with the script.
+fn read_translation(path: &Path) -> Result<StringTable> {
+    let contents = fs::read_to_string(path)?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("po") => Ok(StringTable::parse_po(&contents)?),
+        Some("xlf" | "xliff") => Ok(StringTable::parse_xliff(&contents)?),
+        _ => Ok(serde_json::from_str(&contents)?),
+    }
+}
+
+/// Lists new, changed and removed strings of every translation.
+fn l10n_status(args: &[String]) -> Result<()> {
+    let dlg = load(&args[0])?;
+    let json = args[1..].iter().any(|a| a == "--json");
+    let mut statuses = Vec::new();
+    for path in args[1..].iter().filter(|a| *a != "--json") {
+        let mut status = dlg.translation_status(&read_translation(Path::new(path))?);
+        status.language.get_or_insert_with(|| path.clone());
+        statuses.push(status);
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+    for status in &statuses {
+        let language = status.language.as_deref().unwrap_or_default();
+        if status.is_up_to_date() {
+            println!("{}: up to date", language);
+            continue;
+        }
+        println!(
+            "{}: {} new, {} changed, {} removed",
+            language,
+            status.new.len(),
+            status.changed.len(),
+            status.removed.len()
+        );
+        for (mark, keys) in [
+            ("new", &status.new),
+            ("changed", &status.changed),
+            ("removed", &status.removed),
+        ] {
+            for key in keys {
+                println!("  {:<8} {}", mark, key);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects every .dlgtest file in directory and its subdirectories.
+fn find_tests(dir: &Path, result: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_tests(&path, result)?;
+        } else if path.extension().is_some_and(|e| e == "dlgtest") {
+            result.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn test(dir: &Path) -> Result<()> {
+    let mut tests = Vec::new();
+    find_tests(dir, &mut tests)?;
+    tests.sort();
+    let mut failed = 0;
+    for path in &tests {
+        let result = fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|src| Ok(TranscriptTest::parse(&src)?))
+            .and_then(|test| {
+                let script = path.parent().unwrap_or(Path::new("")).join(&test.script);
+                let dlg = load(&script.to_string_lossy())?;
+                Ok(test.run(&dlg)?)
+            });
+        match result {
+            Result::Ok(None) => println!("{} ... ok", path.display()),
+            Result::Ok(Some(mismatch)) => {
+                failed += 1;
+                println!("{} ... FAILED", path.display());
+                println!("  line {}:", mismatch.line);
+                println!(
+                    "  expected: {}",
+                    mismatch
+                        .expected
+                        .as_deref()
+                        .unwrap_or("<end of transcript>")
+                );
+                println!(
+                    "  actual:   {}",
+                    mismatch.actual.as_deref().unwrap_or("<end of transcript>")
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{} ... FAILED\n  {}", path.display(), e);
+            }
+        }
+    }
+    println!("\n{} passed, {} failed", tests.len() - failed, failed);
+    if failed > 0 {
+        bail!("{} transcript test(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Executes JSON commands from stdin, one per line, and writes JSON messages to stdout.
+fn serve(dlg: &Dialogue) -> Result<()> {
+    let mut runner = Runner::new(dlg);
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = protocol::execute_line(&mut runner, &line);
+        writeln!(stdout, "{}", serde_json::to_string(&message)?)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Playback options. Choices are numbered from 1, like in the prompt.
+#[derive(Default)]
+struct PlayOptions {
+    choices: Vec<usize>,
+    auto: bool,
+    start: Option<String>,
+    transcript: Option<PathBuf>,
+    translation: Option<PathBuf>,
+}
+
+impl PlayOptions {
+    fn parse(args: &[String]) -> Result<PlayOptions> {
+        let mut options = PlayOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--auto" => options.auto = true,
+                "--choices" => {
+                    let value = args.next().context("Missing value of --choices")?;
+                    options.choices = value
+                        .split(',')
+                        .map(|c| match c.trim().parse::<usize>() {
+                            Result::Ok(n) if n > 0 => Ok(n),
+                            _ => Err(anyhow!("Invalid choice '{}'", c)),
+                        })
+                        .collect::<Result<_>>()?;
+                }
+                "--start" => {
+                    options.start = Some(args.next().context("Missing value of --start")?.clone());
+                }
+                "--transcript" => {
+                    options.transcript = Some(PathBuf::from(
+                        args.next().context("Missing value of --transcript")?,
+                    ));
+                }
+                "--translation" => {
+                    options.translation = Some(PathBuf::from(
+                        args.next().context("Missing value of --translation")?,
+                    ));
                 }
+                _ => bail!("Unknown option '{}'", arg),
             }
-            dialasm::DialogueEntry::Jump(l) => {
-                pointer = dlg.label(l).unwrap();
+        }
+        Ok(options)
+    }
+}
+
+/// Reads line from stdin, fails if there is no more input.
+fn read_line() -> Result<String> {
+    let mut buffer = String::new();
+    if io::stdin().read_line(&mut buffer)? == 0 {
+        bail!("Unexpected end of input");
+    }
+    Ok(buffer)
+}
+
+fn play(dlg: &Dialogue, options: PlayOptions) -> Result<()> {
+    let mut runner = Runner::new(dlg);
+    if let Some(path) = &options.translation {
+        runner.set_translation(&load_translation(dlg, path)?);
+    }
+    if let Some(label) = &options.start {
+        runner.goto(label)?;
+    }
+    let mut choices = options.choices.into_iter();
+    let mut transcript = Vec::new();
+    loop {
+        match runner.advance() {
+            Event::Line(line) => {
+                transcript.push(line.to_string());
+                if options.auto {
+                    println!("{}", line);
+                } else {
+                    print!("{}", line);
+                    io::stdout().flush()?;
+                    read_line()?;
+                }
             }
+            Event::Choices(c) => {
+                for (i, option) in c.options.iter().enumerate() {
+                    println!("{}: {}", i + 1, option);
+                }
+                let idx = match choices.next() {
+                    Some(idx) if idx <= c.options.len() => idx,
+                    Some(idx) => bail!("Choice {} is out of range", idx),
+                    None => loop {
+                        io::stdout().flush()?;
+                        if let Result::Ok(idx) = read_line()?.trim_end().parse::<usize>() {
+                            if idx < 1 || idx > c.options.len() {
+                                println!("Invalid choice index");
+                                continue;
+                            }
+                            break idx;
+                        }
+                    },
+                };
+                runner.choose(idx - 1)?;
+                let picked = format!("> {}", c.options[idx - 1]);
+                println!("{}", picked);
+                transcript.push(picked);
+            }
+            Event::End => break,
         }
     }
+    if let Some(path) = options.transcript {
+        let mut contents = transcript.join("\n");
+        contents.push('\n');
+        fs::write(path, contents)?;
+    }
     Ok(())
 }
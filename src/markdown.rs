@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    Dialogue, DialogueEntry,
+    command::command_text,
+    markup::{replace_markup, strip_markup},
+};
+
+/// Markup tags shown as Markdown emphasis, with their markers.
+const EMPHASIS: &[(&str, &str)] = &[("b", "**"), ("i", "*")];
+
+/// Escapes characters which Markdown would take for formatting, links or HTML.
+fn escape(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim_start();
+            let (indent, content) = line.split_at(line.len() - content.len());
+            let mut result = String::with_capacity(line.len());
+            result.push_str(indent);
+            // Lists and underlines of headings only start at the beginning of a line.
+            if content.starts_with(['-', '+', '=']) {
+                result.push('\\');
+            }
+            let digits = content.chars().take_while(char::is_ascii_digit).count();
+            let ordered = digits > 0 && content[digits..].starts_with(['.', ')']);
+            for (i, c) in content.chars().enumerate() {
+                if matches!(
+                    c,
+                    '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+                ) || (ordered && i == digits)
+                {
+                    result.push('\\');
+                }
+                result.push(c);
+            }
+            result
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of a paragraph. Bold and italic markup becomes Markdown emphasis, other tags are
+/// removed. Lines end with hard line breaks, so blank lines don't end the paragraph.
+fn markdown_text(text: &str) -> String {
+    replace_markup(text, EMPHASIS, escape)
+        .lines()
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+/// Anchor which renderers give to a heading: its text in lowercase, without punctuation and
+/// with dashes instead of spaces. Repeated anchors get `-1`, `-2` and so on appended.
+fn anchor(heading: &str, used: &mut HashSet<String>) -> String {
+    let slug: String = heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect();
+    let mut result = slug.clone();
+    let mut suffix = 0;
+    while !used.insert(result.clone()) {
+        suffix += 1;
+        result = format!("{}-{}", slug, suffix);
+    }
+    result
+}
+
+/// Link to the heading of a label. Undefined labels have no heading, so their links lead nowhere.
+fn label_link(label: &str, anchors: &HashMap<&str, String>) -> String {
+    let anchor = anchors
+        .get(label)
+        .cloned()
+        .unwrap_or_else(|| anchor(label, &mut HashSet::new()));
+    format!("[{}](#{})", escape(label), anchor)
+}
+
+impl Dialogue {
+    /// Writes the dialogue as Markdown document for reviews in a browser or pull request.
+    /// Labels become headings, phrases are paragraphs with speaker names at that point, and
+    /// choices are lists of links to the headings of their labels. Jumps are links as well,
+    /// and commands are shown as code.
+    pub fn to_markdown(&self, title: Option<&str>) -> String {
+        let mut labels: Vec<(&str, usize)> = self
+            .labels()
+            .iter()
+            .map(|(l, i)| (l.as_str(), *i))
+            .collect();
+        labels.sort_by_key(|(l, i)| (*i, *l));
+        // Anchors follow the order of headings, so links match the ones renderers make.
+        let mut used = HashSet::new();
+        if let Some(title) = title {
+            anchor(title, &mut used);
+        }
+        let anchors: HashMap<&str, String> = labels
+            .iter()
+            .map(|(l, _)| (*l, anchor(l, &mut used)))
+            .collect();
+        let mut labels = labels.into_iter().peekable();
+        let mut names: HashMap<&str, &str> = HashMap::new();
+        let mut paragraphs = Vec::new();
+        if let Some(title) = title {
+            paragraphs.push(format!("# {}", escape(title)));
+        }
+        for index in 0..=self.len() {
+            while let Some((label, _)) = labels.next_if(|(_, i)| *i == index) {
+                paragraphs.push(format!("## {}", escape(label)));
+            }
+            let Some(entry) = self.get(index) else {
+                break;
+            };
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    names.insert(h, n);
+                }
+                DialogueEntry::Phrase(h, t) => {
+                    let mut paragraph = String::new();
+                    if !h.is_empty() {
+                        let speakers: Vec<&str> = h
+                            .iter()
+                            .map(|h| names.get(h.as_str()).copied().unwrap_or(h))
+                            .collect();
+                        paragraph.push_str(&format!("**{}:** ", escape(&speakers.join(" & "))));
+                    }
+                    paragraph.push_str(&markdown_text(
+                        &self.document_entry_text(index, 0, t, &names),
+                    ));
+                    if let Some(tags) = self.tags(index).filter(|t| !t.is_empty()) {
+                        let tags: Vec<String> =
+                            tags.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                        paragraph.push_str(&format!(" *({})*", escape(&tags.join(", "))));
+                    }
+                    paragraphs.push(paragraph);
+                }
+                DialogueEntry::Choice(c) => {
+                    let options: Vec<String> = c
+                        .iter()
+                        .enumerate()
+                        .map(|(option, choice)| {
+                            let text =
+                                self.document_entry_text(index, option, &choice.text, &names);
+                            format!(
+                                "{}. {} → {}",
+                                option + 1,
+                                escape(&strip_markup(&text)).replace('\n', " "),
+                                label_link(&choice.label, &anchors)
+                            )
+                        })
+                        .collect();
+                    paragraphs.push(options.join("\n"));
+                }
+                DialogueEntry::Jump(l) => paragraphs.push(format!("→ {}", label_link(l, &anchors))),
+                DialogueEntry::ConditionalJump(l, c) => {
+                    paragraphs.push(format!("→ {} if `{}`", label_link(l, &anchors), c))
+                }
+                DialogueEntry::Command(n, a) => {
+                    paragraphs.push(format!("`{}`", command_text(n, a)))
+                }
+                DialogueEntry::AttributeChange(..) => (),
+            }
+        }
+        let mut result = paragraphs.join("\n\n");
+        result.push('\n');
+        result
+    }
+}
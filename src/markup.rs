@@ -0,0 +1,191 @@
+use pest::Parser;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{DialasmParser, Rule};
+
+/// Tags which don't wrap any text and have no closing tag.
+pub const VOID_TAGS: &[&str] = &["wait"];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MarkupError {
+    #[error("Invalid tag '{0}'")]
+    InvalidTag(String),
+    #[error("Tag '{0}' is never closed")]
+    UnclosedTag(String),
+    #[error("Closing tag '{0}' doesn't match any open tag")]
+    UnexpectedClosingTag(String),
+}
+
+/// Markup tag with optional value. Example: [color=red]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// Part of text with the same formatting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Span {
+    /// Text with tags applied to it, outermost first.
+    Text { text: String, tags: Vec<Tag> },
+    /// Void tag at this position of text. Example: [wait=0.5]
+    Marker(Tag),
+}
+
+/// Parses markup tags in text. Tags should be properly nested, `[[` is a literal bracket.
+pub fn parse_markup(text: &str) -> Result<Vec<Span>, MarkupError> {
+    let mut spans = Vec::new();
+    let mut open: Vec<Tag> = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('[') {
+        plain.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(r) = rest.strip_prefix("[[") {
+            plain.push('[');
+            rest = r;
+            continue;
+        }
+        let pair = DialasmParser::parse(Rule::markup_tag, rest)
+            .map_err(|_| {
+                let end = rest.find(']').map_or(rest.len(), |i| i + 1);
+                MarkupError::InvalidTag(rest[..end].to_string())
+            })?
+            .next()
+            .unwrap();
+        rest = &rest[pair.as_str().len()..];
+        push_text(&mut spans, &mut plain, &open);
+        let mut inner = pair.into_inner();
+        let first = inner.next().unwrap();
+        if first.as_rule() == Rule::markup_close {
+            let name = first.into_inner().peek().unwrap().as_str().to_string();
+            if open.last().is_none_or(|t| t.name != name) {
+                return Err(MarkupError::UnexpectedClosingTag(name));
+            }
+            open.pop();
+            continue;
+        }
+        let tag = Tag {
+            name: first.as_str().to_string(),
+            value: inner.next().map(|v| v.as_str().to_string()),
+        };
+        if VOID_TAGS.contains(&tag.name.as_str()) {
+            spans.push(Span::Marker(tag));
+        } else {
+            open.push(tag);
+        }
+    }
+    plain.push_str(rest);
+    push_text(&mut spans, &mut plain, &open);
+    match open.pop() {
+        Some(tag) => Err(MarkupError::UnclosedTag(tag.name)),
+        None => Ok(spans),
+    }
+}
+
+/// Adds text to the last span if it has the same tags, otherwise starts a new one.
+fn push_text(spans: &mut Vec<Span>, plain: &mut String, open: &[Tag]) {
+    if plain.is_empty() {
+        return;
+    }
+    if let Some(Span::Text { text, tags }) = spans.last_mut()
+        && tags == open
+    {
+        text.push_str(plain);
+        plain.clear();
+        return;
+    }
+    spans.push(Span::Text {
+        text: std::mem::take(plain),
+        tags: open.to_vec(),
+    });
+}
+
+/// Text of spans without any markup.
+pub fn plain_text(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .filter_map(|s| match s {
+            Span::Text { text, .. } => Some(text.as_str()),
+            Span::Marker(_) => None,
+        })
+        .collect()
+}
+
+/// Text without markup. Text with invalid markup is returned as is.
+pub(crate) fn strip_markup(text: &str) -> String {
+    parse_markup(text)
+        .map(|s| plain_text(&s))
+        .unwrap_or_else(|_| text.to_string())
+}
+
+/// Text for documents, with tags replaced by `markers` around the text they wrap,
+/// such as `**` for `b` in Markdown. Other tags are removed, and text is escaped.
+/// Text with invalid markup is only escaped.
+pub(crate) fn replace_markup(
+    text: &str,
+    markers: &[(&str, &str)],
+    escape: impl Fn(&str) -> String,
+) -> String {
+    let Ok(spans) = parse_markup(text) else {
+        return escape(text);
+    };
+    let mut result = String::new();
+    // Markers are only opened and closed where tags change, so nested tags stay nested.
+    let mut open: Vec<&str> = Vec::new();
+    for span in &spans {
+        let Span::Text { text, tags } = span else {
+            continue;
+        };
+        let text = escape(text);
+        // Emphasis can't start or end with whitespace, so it's kept outside of markers.
+        if text.trim().is_empty() {
+            result.push_str(&text);
+            continue;
+        }
+        let mut wanted: Vec<&str> = Vec::new();
+        for tag in tags {
+            if let Some((_, marker)) = markers.iter().find(|(t, _)| *t == tag.name)
+                && !wanted.contains(marker)
+            {
+                wanted.push(marker);
+            }
+        }
+        let kept = open.iter().zip(&wanted).take_while(|(a, b)| a == b).count();
+        close_markers(&mut result, &mut open, kept);
+        let trimmed = text.trim_start();
+        if wanted.len() > kept {
+            result.push_str(&text[..text.len() - trimmed.len()]);
+            for marker in &wanted[kept..] {
+                result.push_str(marker);
+                open.push(marker);
+            }
+            result.push_str(trimmed);
+        } else {
+            result.push_str(&text);
+        }
+    }
+    close_markers(&mut result, &mut open, 0);
+    result
+}
+
+/// Closes markers opened after the first `kept`, in reverse order, before trailing whitespace.
+fn close_markers(result: &mut String, open: &mut Vec<&str>, kept: usize) {
+    if open.len() <= kept {
+        return;
+    }
+    let whitespace = result.split_off(result.trim_end().len());
+    while open.len() > kept {
+        result.push_str(open.pop().unwrap());
+    }
+    result.push_str(&whitespace);
+}
+
+/// Whether spans have no tags at all.
+pub(crate) fn is_plain(spans: &[Span]) -> bool {
+    spans
+        .iter()
+        .all(|s| matches!(s, Span::Text { tags, .. } if tags.is_empty()))
+}
@@ -0,0 +1,167 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::{Dialogue, DialogueEntry};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CoverageError {
+    #[error("Playthrough {playthrough}: choice {choice} is out of range at entry {entry}")]
+    InvalidChoice {
+        playthrough: usize,
+        entry: usize,
+        choice: usize,
+    },
+}
+
+/// Single route through the dialogue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialoguePath {
+    /// Indices of every visited entry, in order.
+    pub entries: Vec<usize>,
+    /// Indices of picked options, one per visited choice.
+    pub choices: Vec<usize>,
+    /// `false` if the path was cut because some entry was visited too many times.
+    pub complete: bool,
+}
+
+impl DialoguePath {
+    fn new() -> DialoguePath {
+        DialoguePath {
+            entries: Vec::new(),
+            choices: Vec::new(),
+            complete: true,
+        }
+    }
+
+    /// Last entry of the complete path, `None` if path is cut or empty.
+    pub fn ending(&self) -> Option<usize> {
+        if self.complete {
+            self.entries.last().copied()
+        } else {
+            None
+        }
+    }
+}
+
+/// Entries visited by a set of playthroughs. Created with `Dialogue::coverage()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub covered: BTreeSet<usize>,
+    pub uncovered: BTreeSet<usize>,
+}
+
+impl Coverage {
+    /// Part of covered entries, from 0 to 1. Empty dialogue is fully covered.
+    pub fn ratio(&self) -> f64 {
+        let total = self.covered.len() + self.uncovered.len();
+        if total == 0 {
+            return 1.0;
+        }
+        self.covered.len() as f64 / total as f64
+    }
+}
+
+/// Counts distinct endings of the paths.
+pub fn count_endings(paths: &[DialoguePath]) -> usize {
+    paths
+        .iter()
+        .filter_map(DialoguePath::ending)
+        .collect::<BTreeSet<usize>>()
+        .len()
+}
+
+impl Dialogue {
+    /// Enumerates every path starting from the entry with index `start`.
+    /// Path is cut once any entry is about to be visited more than `max_visits` times.
+    /// Conditional jumps split the path in two, one with the jump taken and one without.
+    pub fn paths(&self, start: usize, max_visits: usize) -> Vec<DialoguePath> {
+        let mut result = Vec::new();
+        let mut stack = vec![(start, DialoguePath::new(), HashMap::new())];
+        while let Some((mut pointer, mut path, mut visits)) = stack.pop() {
+            loop {
+                if pointer >= self.len() {
+                    result.push(path);
+                    break;
+                }
+                let count: &mut usize = visits.entry(pointer).or_default();
+                if *count >= max_visits {
+                    path.complete = false;
+                    result.push(path);
+                    break;
+                }
+                *count += 1;
+                path.entries.push(pointer);
+                match &self[pointer] {
+                    DialogueEntry::Choice(c) => {
+                        for (i, choice) in c.iter().enumerate().skip(1).rev() {
+                            let mut branch = path.clone();
+                            branch.choices.push(i);
+                            stack.push((
+                                self.label(&choice.label).unwrap(),
+                                branch,
+                                visits.clone(),
+                            ));
+                        }
+                        match c.first() {
+                            Some(choice) => {
+                                path.choices.push(0);
+                                pointer = self.label(&choice.label).unwrap();
+                            }
+                            None => pointer += 1,
+                        }
+                    }
+                    DialogueEntry::Jump(l) => pointer = self.label(l).unwrap(),
+                    DialogueEntry::ConditionalJump(l, _) => {
+                        // Condition is not known, so both outcomes are explored.
+                        stack.push((self.label(l).unwrap(), path.clone(), visits.clone()));
+                        pointer += 1;
+                    }
+                    _ => pointer += 1,
+                }
+            }
+        }
+        result
+    }
+
+    /// Enumerates every path starting from label. `None` if there is no such label.
+    pub fn paths_from(&self, label: &str, max_visits: usize) -> Option<Vec<DialoguePath>> {
+        self.label(label).map(|start| self.paths(start, max_visits))
+    }
+
+    /// Replays each playthrough from the beginning and collects visited entries.
+    /// Playthrough is a list of picked options, one per choice. Replay stops when options run out.
+    /// Conditional jumps are never taken during replay.
+    pub fn coverage(&self, playthroughs: &[Vec<usize>]) -> Result<Coverage, CoverageError> {
+        let mut covered = BTreeSet::new();
+        for (i, playthrough) in playthroughs.iter().enumerate() {
+            let mut choices = playthrough.iter();
+            // Entries visited since the last choice. Revisiting one means an endless jump loop.
+            let mut visited = HashSet::new();
+            let mut pointer = 0;
+            while pointer < self.len() && visited.insert(pointer) {
+                covered.insert(pointer);
+                pointer = match &self[pointer] {
+                    DialogueEntry::Choice(c) => {
+                        let Some(&choice) = choices.next() else {
+                            break;
+                        };
+                        let Some(choice) = c.get(choice) else {
+                            return Err(CoverageError::InvalidChoice {
+                                playthrough: i,
+                                entry: pointer,
+                                choice,
+                            });
+                        };
+                        visited.clear();
+                        self.label(&choice.label).unwrap()
+                    }
+                    DialogueEntry::Jump(l) => self.label(l).unwrap(),
+                    _ => pointer + 1,
+                };
+            }
+        }
+        let uncovered = (0..self.len()).filter(|i| !covered.contains(i)).collect();
+        Ok(Coverage { covered, uncovered })
+    }
+}
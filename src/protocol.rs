@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Choices, Event, Line, Runner, RunnerState};
+
+/// Command sent by the host, one JSON object per line:
+/// `{"cmd": "choose", "index": 0}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    /// Executes dialogue until next phrase, choice, or the end.
+    Advance,
+    /// Picks option by its index, starting from 0, then advances.
+    Choose { index: usize },
+    /// Moves execution to the label, then advances.
+    Goto { label: String },
+    /// Requests snapshot of execution.
+    GetState,
+    /// Restores execution from snapshot, then repeats the event it was stopped at.
+    LoadState { state: RunnerState },
+}
+
+/// Message sent to the host, one JSON object per line:
+/// `{"event": "choices", "index": 7, "options": ["I pick A", "I pick B"]}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Message {
+    Line(Line),
+    Choices(Choices),
+    End,
+    State { state: RunnerState },
+    Error { message: String },
+}
+
+impl From<Event> for Message {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Line(l) => Message::Line(l),
+            Event::Choices(c) => Message::Choices(c),
+            Event::End => Message::End,
+        }
+    }
+}
+
+/// Executes protocol command on runner.
+pub fn execute(runner: &mut Runner<'_>, command: Command) -> Message {
+    let result = match command {
+        Command::Advance => Ok(runner.advance()),
+        Command::Choose { index } => runner.choose(index).map(|_| runner.advance()),
+        Command::Goto { label } => runner.goto(&label).map(|_| runner.advance()),
+        Command::GetState => {
+            return Message::State {
+                state: runner.state(),
+            };
+        }
+        Command::LoadState { state } => runner.load_state(state).map(|_| runner.resume()),
+    };
+    match result {
+        Ok(event) => event.into(),
+        Err(e) => Message::Error {
+            message: e.to_string(),
+        },
+    }
+}
+
+/// Parses and executes single line of protocol input.
+pub fn execute_line(runner: &mut Runner<'_>, line: &str) -> Message {
+    match serde_json::from_str(line) {
+        Ok(command) => execute(runner, command),
+        Err(e) => Message::Error {
+            message: e.to_string(),
+        },
+    }
+}
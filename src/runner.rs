@@ -0,0 +1,688 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt,
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    Anchor, CommandHandler, Dialogue, DialogueEntry, ExpressionError, FunctionRegistry, SeenSet,
+    Span, Speaker, StringTable, Value, ValueType,
+    interpolation::render_text,
+    l10n::Translation,
+    markup::{is_plain, parse_markup, plain_text},
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RunError {
+    #[error("Undefined label '{0}'")]
+    UndefinedLabel(String),
+    #[error("There is no choice to make")]
+    NotAtChoice,
+    #[error("Choice {0} is out of range")]
+    InvalidChoice(usize),
+    #[error("Pointer {0} is out of range")]
+    InvalidPointer(usize),
+    #[error(
+        "State was saved for another version of the dialogue (hash {saved}, current is {current})"
+    )]
+    DialogueChanged { saved: String, current: String },
+    #[error("Position of the saved state is gone from the dialogue (id {id:?}, label {label:?})")]
+    UnresolvedAnchor {
+        id: Option<String>,
+        label: Option<String>,
+    },
+    #[error("Can't rewind {0} steps back, history is too short")]
+    HistoryTooShort(usize),
+    #[error("Condition of entry {index} failed to evaluate: {error}")]
+    Evaluation {
+        index: usize,
+        error: ExpressionError,
+    },
+}
+
+/// Whether every option has no markup.
+fn are_plain(spans: &[Vec<Span>]) -> bool {
+    spans.iter().all(|s| is_plain(s))
+}
+
+/// Count of events kept in history by default.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Phrase shown to the player.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Line {
+    /// Index of the phrase entry.
+    pub index: usize,
+    /// Stable id of the phrase entry.
+    pub id: String,
+    /// Handles of the speakers.
+    pub handles: Vec<String>,
+    /// Current names of the speakers. Handle is used if name was never assigned.
+    pub names: Vec<String>,
+    /// Text without markup.
+    pub text: String,
+    /// Text split by markup. Not serialized if there is no markup.
+    #[serde(skip_serializing_if = "is_plain")]
+    pub spans: Vec<Span>,
+    /// Metadata tags of the phrase. Not serialized if empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Line {
+    /// Names of all speakers joined with " & ", empty for anonymous phrases.
+    pub fn speaker(&self) -> String {
+        self.names.join(" & ")
+    }
+}
+
+/// Formats line as "Speaker: text", or ": text" for anonymous phrases.
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.speaker(), self.text)
+    }
+}
+
+/// Options the player has to pick from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Choices {
+    /// Index of the choice entry.
+    pub index: usize,
+    /// Stable id of the choice entry.
+    pub id: String,
+    /// Texts of options without markup.
+    pub options: Vec<String>,
+    /// Texts of options split by markup, parallel to `options`.
+    /// Not serialized if there is no markup.
+    #[serde(skip_serializing_if = "are_plain")]
+    pub spans: Vec<Vec<Span>>,
+    /// Metadata tags of the choice. Not serialized if empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// What the runner stopped at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Line(Line),
+    Choices(Choices),
+    End,
+}
+
+/// Event shown to the player, as kept in runner history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryEntry {
+    Line(Line),
+    Choice {
+        choices: Choices,
+        /// Picked option, `None` if the choice is not made yet.
+        picked: Option<usize>,
+    },
+}
+
+/// Execution state kept for rewinding.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    pointer: usize,
+    shown: bool,
+    speakers: HashMap<String, String>,
+    attributes: HashMap<String, BTreeMap<String, Value>>,
+    variables: HashMap<String, Value>,
+    visited_choices: BTreeSet<(String, usize)>,
+}
+
+/// Snapshot of runner execution. Created with `Runner::state()`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunnerState {
+    /// `Dialogue::content_hash()` of the dialogue the state was saved for.
+    pub dialogue_hash: String,
+    pub pointer: usize,
+    /// Pointer position which survives script edits.
+    pub anchor: Anchor,
+    /// If the phrase under pointer was already shown.
+    pub shown: bool,
+    pub speakers: BTreeMap<String, String>,
+    /// Speaker attributes by handles. Not serialized if empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, BTreeMap<String, Value>>,
+    pub variables: BTreeMap<String, Value>,
+    /// Picked options as pairs of choice id and option index.
+    pub visited_choices: BTreeSet<(String, usize)>,
+}
+
+type ErrorHandler<'a> = Rc<RefCell<dyn FnMut(RunError) + 'a>>;
+
+/// Executes dialogue. Name changes and jumps are handled internally,
+/// commands are passed to the command handler,
+/// phrases and choices are returned to the host as events.
+/// Clones share the command handler and functions with the original.
+#[derive(Clone)]
+pub struct Runner<'a> {
+    dialogue: &'a Dialogue,
+    pointer: usize,
+    /// If the phrase under pointer was already shown.
+    shown: bool,
+    speakers: HashMap<String, String>,
+    attributes: HashMap<String, BTreeMap<String, Value>>,
+    variables: HashMap<String, Value>,
+    visited_choices: BTreeSet<(String, usize)>,
+    history: VecDeque<(HistoryEntry, Snapshot)>,
+    history_limit: usize,
+    seen: SeenSet,
+    command_handler: Option<Rc<RefCell<dyn CommandHandler + 'a>>>,
+    error_handler: Option<ErrorHandler<'a>>,
+    functions: FunctionRegistry<'a>,
+    translation: Translation,
+}
+
+impl fmt::Debug for Runner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Runner")
+            .field("pointer", &self.pointer)
+            .field("shown", &self.shown)
+            .field("speakers", &self.speakers)
+            .field("attributes", &self.attributes)
+            .field("variables", &self.variables)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Runner<'a> {
+    /// Creates runner at the start of dialogue.
+    pub fn new(dialogue: &'a Dialogue) -> Runner<'a> {
+        Runner {
+            dialogue,
+            pointer: 0,
+            shown: false,
+            speakers: HashMap::new(),
+            attributes: HashMap::new(),
+            variables: HashMap::new(),
+            visited_choices: BTreeSet::new(),
+            history: VecDeque::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            seen: SeenSet::new(),
+            command_handler: None,
+            error_handler: None,
+            functions: FunctionRegistry::new(),
+            translation: Translation::default(),
+        }
+    }
+
+    /// Dialogue being executed.
+    pub fn dialogue(&self) -> &'a Dialogue {
+        self.dialogue
+    }
+
+    /// Index of the current entry. Equals dialogue length at the end.
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Current names of speakers by their handles.
+    pub fn speakers(&self) -> &HashMap<String, String> {
+        &self.speakers
+    }
+
+    /// Current attribute value of the speaker.
+    pub fn attribute(&self, handle: &str, key: &str) -> Option<&Value> {
+        self.attributes.get(handle)?.get(key)
+    }
+
+    /// Current name and attributes of the speaker.
+    /// `None` if neither name nor attributes were assigned yet.
+    pub fn speaker(&self, handle: &str) -> Option<Speaker> {
+        let name = self.speakers.get(handle).cloned();
+        let attributes = self.attributes.get(handle).cloned();
+        if name.is_none() && attributes.is_none() {
+            return None;
+        }
+        Some(Speaker {
+            handle: handle.to_string(),
+            name,
+            attributes: attributes.unwrap_or_default(),
+        })
+    }
+
+    /// Value of the variable set by the host.
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    /// Sets variable. Variables are stored in snapshots along with the rest of the state.
+    pub fn set_variable(&mut self, name: &str, value: impl Into<Value>) {
+        self.variables.insert(name.to_string(), value.into());
+    }
+
+    /// All variables by their names.
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.variables
+    }
+
+    /// If the option of the choice entry was ever picked.
+    pub fn is_visited(&self, choice: usize, option: usize) -> bool {
+        self.dialogue
+            .id(choice)
+            .is_some_and(|id| self.visited_choices.contains(&(id.to_string(), option)))
+    }
+
+    /// If there is nothing left to execute.
+    pub fn is_finished(&self) -> bool {
+        self.pointer >= self.dialogue.len()
+    }
+
+    /// Moves execution to the label. Speaker names are kept.
+    pub fn goto(&mut self, label: &str) -> Result<(), RunError> {
+        self.pointer = self
+            .dialogue
+            .label(label)
+            .ok_or_else(|| RunError::UndefinedLabel(label.to_string()))?;
+        self.shown = false;
+        Ok(())
+    }
+
+    /// Executes dialogue until next phrase, choice, or the end.
+    /// Stays at choice until `Runner::choose()` is called.
+    pub fn advance(&mut self) -> Event {
+        let event = self.step();
+        self.record(&event);
+        event
+    }
+
+    /// Fast-forwards through phrases seen before, in this or any other playthrough.
+    /// Stops at the first unseen phrase, choice, or the end.
+    pub fn skip_seen(&mut self) -> Event {
+        loop {
+            let event = self.step();
+            let seen = matches!(&event, Event::Line(l) if self.seen.contains(&l.id));
+            self.record(&event);
+            if !seen {
+                return event;
+            }
+        }
+    }
+
+    /// Executes dialogue until next phrase, choice, or the end, without recording anything.
+    fn step(&mut self) -> Event {
+        if self.shown {
+            self.pointer += 1;
+            self.shown = false;
+        }
+        let mut steps = 0;
+        while let Some(entry) = self.dialogue.get(self.pointer) {
+            // Only jumps without phrases in between can execute more entries than there are.
+            steps += 1;
+            if steps > self.dialogue.len() {
+                self.pointer = self.dialogue.len();
+                break;
+            }
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    self.speakers.insert(h.clone(), n.clone());
+                    self.pointer += 1;
+                }
+                DialogueEntry::AttributeChange(h, a) => {
+                    self.attributes
+                        .entry(h.clone())
+                        .or_default()
+                        .extend(a.iter().map(|(k, v)| (k.clone(), v.clone())));
+                    self.pointer += 1;
+                }
+                DialogueEntry::Jump(l) => {
+                    self.pointer = self.dialogue.label(l).unwrap_or(self.dialogue.len());
+                }
+                DialogueEntry::ConditionalJump(l, c) => {
+                    // Conditions which fail to evaluate are false, the host is told why.
+                    let condition = match c.evaluate(&self.variables, &self.functions) {
+                        Ok(Value::Bool(b)) => b,
+                        result => {
+                            let error = result.map_or_else(
+                                |e| e,
+                                |v| ExpressionError::Type {
+                                    expected: ValueType::Bool,
+                                    found: v.value_type(),
+                                },
+                            );
+                            if let Some(handler) = &self.error_handler {
+                                (handler.borrow_mut())(RunError::Evaluation {
+                                    index: self.pointer,
+                                    error,
+                                });
+                            }
+                            false
+                        }
+                    };
+                    if condition {
+                        self.pointer = self.dialogue.label(l).unwrap_or(self.dialogue.len());
+                    } else {
+                        self.pointer += 1;
+                    }
+                }
+                DialogueEntry::Phrase(..) => {
+                    self.shown = true;
+                    break;
+                }
+                DialogueEntry::Choice(..) => break,
+                DialogueEntry::Command(n, a) => {
+                    if let Some(handler) = &self.command_handler {
+                        handler.borrow_mut().command(n, a);
+                    }
+                    self.pointer += 1;
+                }
+            }
+        }
+        self.current()
+    }
+
+    /// Event the runner is stopped at. If it's not stopped at any, executes until the next one.
+    pub fn resume(&mut self) -> Event {
+        match self.dialogue.get(self.pointer) {
+            Some(DialogueEntry::Phrase(..)) if self.shown => self.current(),
+            Some(DialogueEntry::Choice(..)) | None => self.current(),
+            _ => self.advance(),
+        }
+    }
+
+    /// Current name of the speaker, translated if there is translation for it.
+    /// Handle is used if name was never assigned.
+    fn name(&self, handle: &str) -> String {
+        match self.speakers.get(handle) {
+            Some(name) => self
+                .translation
+                .name(handle, name)
+                .unwrap_or(name)
+                .to_string(),
+            None => handle.to_string(),
+        }
+    }
+
+    /// Text of phrase or choice option under pointer with interpolations evaluated,
+    /// split by markup. Translated text is preferred.
+    /// `source` is used if the text wasn't parsed, as in `Dialogue::example()`.
+    fn spans(&self, index: usize, source: &str) -> Vec<Span> {
+        let translated = self
+            .dialogue
+            .text_key(self.pointer, index)
+            .and_then(|k| self.translation.text(&k));
+        let parts = translated.or_else(|| {
+            self.dialogue
+                .meta(self.pointer)
+                .and_then(|m| m.texts.get(index))
+                .map(Vec::as_slice)
+        });
+        let text = match parts {
+            Some(parts) => render_text(parts, |h| self.name(h), &self.variables, &self.functions),
+            None => source.to_string(),
+        };
+        parse_markup(&text).unwrap_or_else(|_| {
+            vec![Span::Text {
+                text,
+                tags: Vec::new(),
+            }]
+        })
+    }
+
+    /// Metadata tags of the entry under pointer.
+    fn tags(&self) -> BTreeMap<String, String> {
+        self.dialogue
+            .tags(self.pointer)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Event under pointer, without executing anything.
+    /// Returns `Event::End` if pointer is not at phrase or choice.
+    pub fn current(&self) -> Event {
+        match self.dialogue.get(self.pointer) {
+            Some(DialogueEntry::Phrase(h, t)) => {
+                let spans = self.spans(0, t);
+                Event::Line(Line {
+                    index: self.pointer,
+                    id: self
+                        .dialogue
+                        .id(self.pointer)
+                        .unwrap_or_default()
+                        .to_string(),
+                    handles: h.clone(),
+                    names: h.iter().map(|h| self.name(h)).collect(),
+                    text: plain_text(&spans),
+                    spans,
+                    tags: self.tags(),
+                })
+            }
+            Some(DialogueEntry::Choice(c)) => {
+                let spans: Vec<Vec<Span>> = c
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| self.spans(i, &c.text))
+                    .collect();
+                Event::Choices(Choices {
+                    index: self.pointer,
+                    id: self
+                        .dialogue
+                        .id(self.pointer)
+                        .unwrap_or_default()
+                        .to_string(),
+                    options: spans.iter().map(|s| plain_text(s)).collect(),
+                    spans,
+                    tags: self.tags(),
+                })
+            }
+            _ => Event::End,
+        }
+    }
+
+    /// Takes snapshot of execution.
+    pub fn state(&self) -> RunnerState {
+        RunnerState {
+            dialogue_hash: self.dialogue.content_hash(),
+            pointer: self.pointer,
+            anchor: self.dialogue.anchor(self.pointer),
+            shown: self.shown,
+            speakers: self
+                .speakers
+                .iter()
+                .map(|(h, n)| (h.clone(), n.clone()))
+                .collect(),
+            attributes: self
+                .attributes
+                .iter()
+                .map(|(h, a)| (h.clone(), a.clone()))
+                .collect(),
+            variables: self
+                .variables
+                .iter()
+                .map(|(n, v)| (n.clone(), v.clone()))
+                .collect(),
+            visited_choices: self.visited_choices.clone(),
+        }
+    }
+
+    /// Restores execution from snapshot.
+    /// Fails if the snapshot was taken for a different dialogue.
+    pub fn load_state(&mut self, state: RunnerState) -> Result<(), RunError> {
+        let current = self.dialogue.content_hash();
+        if state.dialogue_hash != current {
+            return Err(RunError::DialogueChanged {
+                saved: state.dialogue_hash,
+                current,
+            });
+        }
+        if state.pointer > self.dialogue.len() {
+            return Err(RunError::InvalidPointer(state.pointer));
+        }
+        self.restore(state.pointer, state);
+        Ok(())
+    }
+
+    /// Restores execution from snapshot, which may be taken for an edited version of the dialogue.
+    /// Pointer is moved to the closest equivalent position using the anchor.
+    /// If the exact position is gone, the phrase at the new position is shown again.
+    pub fn load_state_remapped(&mut self, state: RunnerState) -> Result<(), RunError> {
+        if state.dialogue_hash == self.dialogue.content_hash() {
+            return self.load_state(state);
+        }
+        let pointer = self.dialogue.resolve_anchor(&state.anchor).ok_or_else(|| {
+            RunError::UnresolvedAnchor {
+                id: state.anchor.id.clone(),
+                label: state.anchor.label.clone(),
+            }
+        })?;
+        let exact = state.anchor.id.is_some()
+            && self.dialogue.anchor(pointer).id == state.anchor.id
+            && state.anchor.before_id == 0;
+        let shown = state.shown && exact;
+        self.restore(pointer, state);
+        self.shown = shown;
+        Ok(())
+    }
+
+    fn restore(&mut self, pointer: usize, state: RunnerState) {
+        self.pointer = pointer;
+        self.shown = state.shown;
+        self.speakers = state.speakers.into_iter().collect();
+        self.attributes = state.attributes.into_iter().collect();
+        self.variables = state.variables.into_iter().collect();
+        self.visited_choices = state.visited_choices;
+        self.history.clear();
+    }
+
+    /// Shown lines and choices, oldest first. The last one is the current event.
+    pub fn history(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.history.iter().map(|(e, _)| e)
+    }
+
+    /// Sets how many events are kept in history. Oldest events are forgotten first.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    /// Goes back by `steps` events in history, restoring the whole state as it was then.
+    /// Rewinding to a choice makes it not picked. Returns the event rewound to.
+    pub fn rewind(&mut self, steps: usize) -> Result<Event, RunError> {
+        if steps >= self.history.len() {
+            return Err(RunError::HistoryTooShort(steps));
+        }
+        self.history.truncate(self.history.len() - steps);
+        let (entry, snapshot) = self.history.back_mut().unwrap();
+        if let HistoryEntry::Choice { picked, .. } = entry {
+            *picked = None;
+        }
+        let snapshot = snapshot.clone();
+        self.pointer = snapshot.pointer;
+        self.shown = snapshot.shown;
+        self.speakers = snapshot.speakers;
+        self.attributes = snapshot.attributes;
+        self.variables = snapshot.variables;
+        self.visited_choices = snapshot.visited_choices;
+        Ok(self.current())
+    }
+
+    /// Sets handler of `do` statements. Without handler they are skipped.
+    /// Commands are not undone by rewinding or loading state.
+    pub fn set_command_handler(&mut self, handler: impl CommandHandler + 'a) {
+        self.command_handler = Some(Rc::new(RefCell::new(handler)));
+    }
+
+    /// Sets handler of errors which don't stop the dialogue: conditions which fail
+    /// to evaluate are false, and the handler is called with `RunError::Evaluation`.
+    pub fn set_error_handler(&mut self, handler: impl FnMut(RunError) + 'a) {
+        self.error_handler = Some(Rc::new(RefCell::new(handler)));
+    }
+
+    /// Sets functions callable from expressions.
+    /// Parse with `ParseOptions::functions()` to check the dialogue against them beforehand.
+    pub fn set_functions(&mut self, functions: FunctionRegistry<'a>) {
+        self.functions = functions;
+    }
+
+    /// Functions callable from expressions.
+    pub fn functions(&self) -> &FunctionRegistry<'a> {
+        &self.functions
+    }
+
+    /// Shows texts and speaker names from the translated string table.
+    /// Flow of the dialogue is not affected, and strings missing from the table,
+    /// or failing to parse, are shown untranslated. Empty table removes translation.
+    pub fn set_translation(&mut self, table: &StringTable) {
+        self.translation = Translation::new(self.dialogue, table);
+    }
+
+    /// Phrases seen by the player.
+    pub fn seen(&self) -> &SeenSet {
+        &self.seen
+    }
+
+    /// Replaces phrases seen by the player, usually with ones loaded from persistent storage.
+    pub fn set_seen(&mut self, seen: SeenSet) {
+        self.seen = seen;
+    }
+
+    /// Adds event to history and marks phrases as seen.
+    /// Choice is added once, no matter how many times it was shown.
+    fn record(&mut self, event: &Event) {
+        let entry = match event {
+            Event::Line(l) => {
+                self.seen.insert(&l.id);
+                HistoryEntry::Line(l.clone())
+            }
+            Event::Choices(c) => {
+                if let Some((
+                    HistoryEntry::Choice {
+                        choices,
+                        picked: None,
+                    },
+                    _,
+                )) = self.history.back()
+                    && choices.index == c.index
+                {
+                    return;
+                }
+                HistoryEntry::Choice {
+                    choices: c.clone(),
+                    picked: None,
+                }
+            }
+            Event::End => return,
+        };
+        if self.history_limit == 0 {
+            return;
+        }
+        if self.history.len() == self.history_limit {
+            self.history.pop_front();
+        }
+        let snapshot = Snapshot {
+            pointer: self.pointer,
+            shown: self.shown,
+            speakers: self.speakers.clone(),
+            attributes: self.attributes.clone(),
+            variables: self.variables.clone(),
+            visited_choices: self.visited_choices.clone(),
+        };
+        self.history.push_back((entry, snapshot));
+    }
+
+    /// Picks option of current choice by its index, starting from 0.
+    pub fn choose(&mut self, option: usize) -> Result<(), RunError> {
+        let Some(DialogueEntry::Choice(c)) = self.dialogue.get(self.pointer) else {
+            return Err(RunError::NotAtChoice);
+        };
+        let choice = c.get(option).ok_or(RunError::InvalidChoice(option))?;
+        if let Some(id) = self.dialogue.id(self.pointer) {
+            self.visited_choices.insert((id.to_string(), option));
+        }
+        if let Some((HistoryEntry::Choice { choices, picked }, _)) = self.history.back_mut()
+            && choices.index == self.pointer
+        {
+            *picked = Some(option);
+        }
+        self.goto(&choice.label)
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Ids of phrases the player has ever seen, across all playthroughs.
+/// Meant to be stored separately from saves, serializes as a list of ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SeenSet {
+    ids: BTreeSet<String>,
+}
+
+impl SeenSet {
+    pub fn new() -> SeenSet {
+        SeenSet::default()
+    }
+
+    /// Marks phrase as seen. Returns `false` if it was seen before.
+    pub fn insert(&mut self, id: &str) -> bool {
+        self.ids.insert(id.to_string())
+    }
+
+    /// If phrase was seen.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Ids of seen phrases.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.ids.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
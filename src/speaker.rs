@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{Dialogue, DialogueEntry, Value};
+
+/// Speaker with its name and attributes, such as portrait or text color.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Speaker {
+    pub handle: String,
+    /// `None` if name was never assigned.
+    pub name: Option<String>,
+    pub attributes: BTreeMap<String, Value>,
+}
+
+impl Dialogue {
+    /// Every speaker with a name or attributes assigned, sorted by handle.
+    /// Speakers are described as declared: by the first name assigned to them,
+    /// and the first value of each attribute. Later changes are applied by the runner.
+    pub fn speakers(&self) -> Vec<Speaker> {
+        let mut speakers: BTreeMap<&str, Speaker> = BTreeMap::new();
+        for entry in self.entries() {
+            let (handle, name, attributes) = match entry {
+                DialogueEntry::NameChange(h, n) => (h, Some(n), None),
+                DialogueEntry::AttributeChange(h, a) => (h, None, Some(a)),
+                _ => continue,
+            };
+            let speaker = speakers.entry(handle).or_insert_with(|| Speaker {
+                handle: handle.clone(),
+                name: None,
+                attributes: BTreeMap::new(),
+            });
+            if speaker.name.is_none() {
+                speaker.name = name.cloned();
+            }
+            for (key, value) in attributes.into_iter().flatten() {
+                speaker
+                    .attributes
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        speakers.into_values().collect()
+    }
+
+    /// Speaker as declared, see `Dialogue::speakers()`.
+    pub fn speaker(&self, handle: &str) -> Option<Speaker> {
+        self.speakers().into_iter().find(|s| s.handle == handle)
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{Dialogue, DialogueEntry, markup::strip_markup};
+
+/// Average reading speed used for reading time estimation.
+pub const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// Line and word counts of a single speaker.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeakerStats {
+    /// Speaker handle, `None` for anonymous phrases.
+    pub handle: Option<String>,
+    /// First name assigned to the speaker, `None` for anonymous phrases.
+    pub name: Option<String>,
+    /// Count of phrases spoken (including group phrases).
+    pub lines: usize,
+    /// Count of words spoken (including group phrases).
+    pub words: usize,
+}
+
+/// Statistics of the whole dialogue. Created with `Dialogue::stats()`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DialogueStats {
+    /// Per speaker counts. Anonymous speaker goes first, the rest are sorted by handle.
+    pub speakers: Vec<SpeakerStats>,
+    /// Count of phrases.
+    pub lines: usize,
+    /// Count of words in phrases. Group phrases are counted once.
+    pub words: usize,
+    /// Count of choice statements.
+    pub choices: usize,
+    /// Count of options in all choice statements.
+    pub options: usize,
+    /// Count of words in choice texts.
+    pub choice_words: usize,
+    /// Average count of options per choice statement.
+    pub branching_factor: f64,
+    /// Count of labels.
+    pub labels: usize,
+    /// Estimated time to read every phrase and choice, in seconds.
+    pub reading_time: f64,
+}
+
+/// Counts words separated by whitespace. Markup is not counted, expressions count as words.
+pub fn word_count(text: &str) -> usize {
+    strip_markup(text).split_whitespace().count()
+}
+
+impl Dialogue {
+    /// Collects statistics of the dialogue.
+    pub fn stats(&self) -> DialogueStats {
+        let mut names: BTreeMap<&str, &str> = BTreeMap::new();
+        let mut speakers: BTreeMap<Option<&str>, SpeakerStats> = BTreeMap::new();
+        let mut lines = 0;
+        let mut words = 0;
+        let mut choices = 0;
+        let mut options = 0;
+        let mut choice_words = 0;
+        for entry in self.entries() {
+            match entry {
+                DialogueEntry::NameChange(h, n) => {
+                    names.entry(h).or_insert(n);
+                }
+                DialogueEntry::Phrase(h, t) => {
+                    let count = word_count(t);
+                    lines += 1;
+                    words += count;
+                    let handles: Vec<Option<&str>> = if h.is_empty() {
+                        vec![None]
+                    } else {
+                        h.iter().map(|h| Some(h.as_str())).collect()
+                    };
+                    for handle in handles {
+                        let stats = speakers.entry(handle).or_insert_with(|| SpeakerStats {
+                            handle: handle.map(str::to_string),
+                            name: handle.and_then(|h| names.get(h)).map(|n| n.to_string()),
+                            lines: 0,
+                            words: 0,
+                        });
+                        stats.lines += 1;
+                        stats.words += count;
+                    }
+                }
+                DialogueEntry::Choice(c) => {
+                    choices += 1;
+                    options += c.len();
+                    choice_words += c.iter().map(|c| word_count(&c.text)).sum::<usize>();
+                }
+                DialogueEntry::Jump(_)
+                | DialogueEntry::ConditionalJump(..)
+                | DialogueEntry::Command(..)
+                | DialogueEntry::AttributeChange(..) => (),
+            }
+        }
+        let branching_factor = if choices == 0 {
+            0.0
+        } else {
+            options as f64 / choices as f64
+        };
+        DialogueStats {
+            speakers: speakers.into_values().collect(),
+            lines,
+            words,
+            choices,
+            options,
+            choice_words,
+            branching_factor,
+            labels: self.label_count(),
+            reading_time: (words + choice_words) as f64 / WORDS_PER_MINUTE * 60.0,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use thiserror::Error;
+
+use crate::{Dialogue, Event, RunError, Runner};
+
+/// Separates header of transcript test from expected transcript.
+pub const TRANSCRIPT_SEPARATOR: &str = "---";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TranscriptError {
+    #[error("Missing '{TRANSCRIPT_SEPARATOR}' line between header and transcript")]
+    MissingSeparator,
+    #[error("Missing 'script' in header")]
+    MissingScript,
+    #[error("Invalid header line '{0}'")]
+    InvalidHeader(String),
+    #[error("Invalid choice '{0}', choices are numbered from 1")]
+    InvalidChoice(String),
+}
+
+/// Scripted playthrough with expected transcript. Parsed from `.dlgtest` files:
+/// ```text
+/// script: intro.dlg
+/// start: intro
+/// choices: 1, 2, 1
+/// ---
+/// Maria: Hello!
+/// > I pick A
+/// : Phrase told by nobody.
+/// ```
+/// `start` and `choices` can be omitted. Choices are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptTest {
+    /// Path to the script, relative to the test file.
+    pub script: String,
+    /// Label to start from. Dialogue starts from the beginning if there is none.
+    pub start: Option<String>,
+    /// Options to pick, starting from 0.
+    pub choices: Vec<usize>,
+    /// Expected transcript lines.
+    pub expected: Vec<String>,
+}
+
+/// First difference between expected and actual transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptMismatch {
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// `None` if actual transcript has more lines than expected.
+    pub expected: Option<String>,
+    /// `None` if actual transcript has less lines than expected.
+    pub actual: Option<String>,
+}
+
+impl TranscriptTest {
+    /// Parses transcript test source.
+    pub fn parse(src: &str) -> Result<TranscriptTest, TranscriptError> {
+        let mut lines = src.lines();
+        let mut script = None;
+        let mut start = None;
+        let mut choices = Vec::new();
+        loop {
+            let line = lines.next().ok_or(TranscriptError::MissingSeparator)?;
+            let line = line.trim();
+            if line == TRANSCRIPT_SEPARATOR {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(TranscriptError::InvalidHeader(line.to_string()));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "script" => script = Some(value.to_string()),
+                "start" => start = Some(value.to_string()),
+                "choices" => {
+                    choices = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(|c| match c.parse::<usize>() {
+                            Ok(n) if n > 0 => Ok(n - 1),
+                            _ => Err(TranscriptError::InvalidChoice(c.to_string())),
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                _ => return Err(TranscriptError::InvalidHeader(line.to_string())),
+            }
+        }
+        let mut expected: Vec<String> = lines.map(|l| l.trim_end().to_string()).collect();
+        while expected.last().is_some_and(|l| l.is_empty()) {
+            expected.pop();
+        }
+        Ok(TranscriptTest {
+            script: script.ok_or(TranscriptError::MissingScript)?,
+            start,
+            choices,
+            expected,
+        })
+    }
+
+    /// Plays dialogue and compares its transcript with expected one.
+    pub fn run(&self, dialogue: &Dialogue) -> Result<Option<TranscriptMismatch>, RunError> {
+        let actual = transcript(dialogue, self.start.as_deref(), &self.choices)?;
+        Ok(compare_transcripts(&self.expected, &actual))
+    }
+}
+
+/// Plays dialogue with predefined options (starting from 0) and records every line.
+/// Phrases are written as "Speaker: text", picked options as "> text".
+/// Playback stops at the first choice after options run out.
+pub fn transcript(
+    dialogue: &Dialogue,
+    start: Option<&str>,
+    choices: &[usize],
+) -> Result<Vec<String>, RunError> {
+    let mut runner = Runner::new(dialogue);
+    if let Some(label) = start {
+        runner.goto(label)?;
+    }
+    let mut choices = choices.iter();
+    let mut result = Vec::new();
+    loop {
+        match runner.advance() {
+            Event::Line(line) => result.push(line.to_string()),
+            Event::Choices(c) => {
+                let Some(&choice) = choices.next() else {
+                    break;
+                };
+                runner.choose(choice)?;
+                result.push(format!("> {}", c.options[choice]));
+            }
+            Event::End => break,
+        }
+    }
+    Ok(result)
+}
+
+/// Finds the first differing line of two transcripts.
+pub fn compare_transcripts(expected: &[String], actual: &[String]) -> Option<TranscriptMismatch> {
+    (0..expected.len().max(actual.len())).find_map(|i| {
+        let (e, a) = (expected.get(i), actual.get(i));
+        (e != a).then(|| TranscriptMismatch {
+            line: i + 1,
+            expected: e.cloned(),
+            actual: a.cloned(),
+        })
+    })
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use pest::iterators::Pair;
+use serde::{Deserialize, Serialize};
+
+use crate::Rule;
+
+/// Value of a variable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+/// Type of a value, used in signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Bool,
+    Number,
+    String,
+}
+
+impl Value {
+    /// Builds value from parsed literal.
+    pub(crate) fn from_literal(pair: Pair<'_, Rule>) -> Value {
+        let literal = pair.into_inner().peek().unwrap();
+        match literal.as_rule() {
+            Rule::string_literal => {
+                Value::String(literal.into_inner().peek().unwrap().as_str().to_string())
+            }
+            Rule::number_literal => Value::Number(literal.as_str().parse().unwrap()),
+            _ => Value::Bool(literal.as_str() == "true"),
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Bool(_) => ValueType::Bool,
+            Value::Number(_) => ValueType::Number,
+            Value::String(_) => ValueType::String,
+        }
+    }
+}
+
+/// Numbers are equal as floats, except that NaN is equal to itself,
+/// so values can be compared in entries deriving `Eq`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::String(a), Value::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::Bool => write!(f, "bool"),
+            ValueType::Number => write!(f, "number"),
+            ValueType::String => write!(f, "string"),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    Dialogue, DialogueEntry, csv::quote, interpolation::document_text, markup::strip_markup,
+};
+
+/// Columns of voice-over manifest, in order.
+pub const VO_MANIFEST_COLUMNS: [&str; 4] = ["id", "speaker", "file", "text"];
+
+/// Extensions of files considered recordings by `VoScript::check_audio()`.
+pub const AUDIO_EXTENSIONS: &[&str] = &["wav", "ogg", "mp3", "flac", "opus", "m4a", "aiff"];
+
+/// Path of the recording of the phrase by the speaker, relative to the audio directory.
+/// Ids set with `#id:` are kept across script edits, and so are the file names. Generated ids
+/// change with the text, which renames the recording, so voiced phrases should have explicit ids.
+pub fn vo_file(handle: &str, id: &str, extension: &str) -> String {
+    format!("{}/{}.{}", handle, id, extension)
+}
+
+/// Phrase an actor has to record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VoLine {
+    /// Phrase id.
+    pub id: String,
+    /// Whether the id is set in the script with `#id:`. Otherwise the file is renamed
+    /// whenever the text is edited.
+    pub explicit_id: bool,
+    /// Path of the recording, see `vo_file()`.
+    pub file: String,
+    /// Text as the actor reads it: speakers are replaced with their names, markup is removed,
+    /// and expressions are shown as written.
+    pub text: String,
+    /// Other speakers of group phrases, named like `Leon (@l)`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<String>,
+    /// Label of the section the phrase is in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Line of the phrase in the source, starting from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Metadata tags of the phrase, which may contain directions for the actor.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Every phrase spoken by the speaker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VoActor {
+    pub handle: String,
+    pub name: Option<String>,
+    pub lines: Vec<VoLine>,
+}
+
+/// Recordings found for the voice-over script. Created with `VoScript::check_audio()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct AudioReport {
+    /// Expected files which have no recording.
+    pub missing: Vec<String>,
+    /// Recordings which don't belong to any phrase, such as ones of removed phrases.
+    pub orphaned: Vec<String>,
+    /// Expected files with more than one recording, such as both `m/hi.wav` and `m/hi.ogg`.
+    pub duplicates: BTreeMap<String, Vec<String>>,
+}
+
+impl AudioReport {
+    /// Whether every phrase has exactly one recording, and there are no others.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.orphaned.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// Path without extension, which recordings are matched by.
+fn stem(file: &str) -> &str {
+    let name = file.rfind('/').map_or(0, |i| i + 1);
+    match file[name..].rfind('.') {
+        Some(dot) if dot > 0 => &file[..name + dot],
+        _ => file,
+    }
+}
+
+/// Whether file has one of `AUDIO_EXTENSIONS`.
+fn is_audio(file: &str) -> bool {
+    file.get(stem(file).len() + 1..)
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Recording script of the dialogue. Created with `Dialogue::vo_script()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct VoScript {
+    /// Speakers who have phrases, sorted by handle.
+    pub actors: Vec<VoActor>,
+}
+
+impl VoActor {
+    /// Name and handle, as speakers are shown to people.
+    fn title(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} (@{})", name, self.handle),
+            None => format!("@{}", self.handle),
+        }
+    }
+
+    /// Name of the actor document, which is the same for every version of the script.
+    pub fn file_name(&self) -> String {
+        format!("{}.md", self.handle)
+    }
+
+    /// Markdown document with every phrase of the actor, grouped by sections.
+    pub fn to_markdown(&self) -> String {
+        let mut result = format!("# {}\n\n", self.title());
+        result.push_str(&match self.lines.len() {
+            1 => String::from("1 line\n"),
+            n => format!("{} lines\n", n),
+        });
+        let mut section = None;
+        for line in &self.lines {
+            if line.label.is_some() && line.label != section {
+                section = line.label.clone();
+                result.push_str(&format!("\n## {}\n", line.label.as_deref().unwrap()));
+            }
+            result.push_str(&format!("\n### {}\n\n", line.id));
+            result.push_str(&format!("- File: `{}`\n", line.file));
+            if !line.with.is_empty() {
+                result.push_str(&format!("- With: {}\n", line.with.join(", ")));
+            }
+            if !line.tags.is_empty() {
+                let tags: Vec<String> = line
+                    .tags
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect();
+                result.push_str(&format!("- Direction: {}\n", tags.join(", ")));
+            }
+            result.push('\n');
+            for text in line.text.lines() {
+                result.push_str(&format!("> {}\n", text));
+            }
+        }
+        result
+    }
+}
+
+impl VoScript {
+    /// CSV with `VO_MANIFEST_COLUMNS`, which maps phrases to files expected from each actor.
+    pub fn manifest(&self) -> String {
+        let mut result = VO_MANIFEST_COLUMNS.join(",");
+        result.push('\n');
+        for actor in &self.actors {
+            for line in &actor.lines {
+                let fields = [
+                    line.id.as_str(),
+                    actor.handle.as_str(),
+                    line.file.as_str(),
+                    line.text.as_str(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|f| quote(f, ',')).collect();
+                result.push_str(&fields.join(","));
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    /// Matches recordings with phrases. `files` are paths relative to the audio directory,
+    /// separated with `/`. Files are matched by path without extension,
+    /// and ones without `AUDIO_EXTENSIONS` are ignored.
+    pub fn check_audio(&self, files: &[String]) -> AudioReport {
+        let mut found: HashMap<&str, Vec<String>> = HashMap::new();
+        for file in files {
+            if is_audio(file) {
+                found.entry(stem(file)).or_default().push(file.clone());
+            }
+        }
+        let mut report = AudioReport::default();
+        for line in self.lines() {
+            match found.get(stem(&line.file)) {
+                None => report.missing.push(line.file.clone()),
+                Some(recordings) if recordings.len() > 1 => {
+                    let mut recordings = recordings.clone();
+                    recordings.sort();
+                    report.duplicates.insert(line.file.clone(), recordings);
+                }
+                Some(_) => (),
+            }
+        }
+        let expected: HashSet<&str> = self.lines().map(|l| stem(&l.file)).collect();
+        report.orphaned = found
+            .into_iter()
+            .filter(|(stem, _)| !expected.contains(stem))
+            .flat_map(|(_, files)| files)
+            .collect();
+        report.orphaned.sort();
+        report
+    }
+
+    /// Every expected recording, in order of actors.
+    pub fn lines(&self) -> impl Iterator<Item = &VoLine> {
+        self.actors.iter().flat_map(|a| &a.lines)
+    }
+}
+
+impl Dialogue {
+    /// Groups phrases by speakers for recording. Group phrases are listed for each of
+    /// their speakers, who record them separately. Anonymous phrases are not voiced.
+    /// Recordings are expected to have `extension`, such as "wav" or "ogg".
+    pub fn vo_script(&self, extension: &str) -> VoScript {
+        let speakers = self.speakers();
+        let names: HashMap<&str, &str> = speakers
+            .iter()
+            .filter_map(|s| Some((s.handle.as_str(), s.name.as_deref()?)))
+            .collect();
+        let title = |h: &str| match names.get(h) {
+            Some(name) => format!("{} (@{})", name, h),
+            None => format!("@{}", h),
+        };
+        let mut lines: BTreeMap<&str, Vec<VoLine>> = BTreeMap::new();
+        for (index, entry) in self.entries().iter().enumerate() {
+            let DialogueEntry::Phrase(handles, text) = entry else {
+                continue;
+            };
+            let Some(id) = self.id(index) else {
+                continue;
+            };
+            let meta = self.meta(index);
+            let rendered = match meta.and_then(|m| m.texts.first()) {
+                Some(parts) => document_text(parts, |h| {
+                    names.get(h).map_or(format!("@{}", h), |n| n.to_string())
+                }),
+                None => text.replace("\\\"", "\""),
+            };
+            let rendered = strip_markup(&rendered);
+            for handle in handles {
+                lines.entry(handle).or_default().push(VoLine {
+                    id: id.to_string(),
+                    explicit_id: meta.is_some_and(|m| m.explicit_id),
+                    file: vo_file(handle, id, extension),
+                    text: rendered.clone(),
+                    with: handles
+                        .iter()
+                        .filter(|h| *h != handle)
+                        .map(|h| title(h))
+                        .collect(),
+                    label: self.section(index).map(str::to_string),
+                    line: meta.and_then(|m| m.line),
+                    tags: meta.map(|m| m.tags.clone()).unwrap_or_default(),
+                });
+            }
+        }
+        VoScript {
+            actors: lines
+                .into_iter()
+                .map(|(handle, lines)| VoActor {
+                    handle: handle.to_string(),
+                    name: names.get(handle).map(|n| n.to_string()),
+                    lines,
+                })
+                .collect(),
+        }
+    }
+}
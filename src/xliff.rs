@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::{StringEntry, StringKind, StringTable, source_hash};
+
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+#[derive(Error, Debug)]
+pub enum XliffError {
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("Document is not XLIFF 2.0")]
+    NotXliff,
+    #[error("Unit at line {0} has no id")]
+    MissingUnitId(usize),
+    #[error("Duplicate unit '{0}'")]
+    DuplicateUnit(String),
+}
+
+/// Problems found while importing XLIFF document.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct XliffReport {
+    /// Units which don't exist in the script anymore. Their translations are not used.
+    pub unknown: Vec<String>,
+    /// Units translated from a different source text than the script has now.
+    /// Their translations are still used.
+    pub stale: Vec<String>,
+    /// Strings of the script which have no translation, and keep the source text.
+    pub missing: Vec<String>,
+}
+
+impl XliffReport {
+    /// Whether every string is translated from the current source text.
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.stale.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Unit id for the key. Ids are XML name tokens, so speaker name keys (`@m`) become `name:m`.
+fn unit_id(key: &str) -> String {
+    match key.strip_prefix('@') {
+        Some(name) => format!("name:{}", name),
+        None => key.to_string(),
+    }
+}
+
+/// Reverses `unit_id()`.
+fn unit_key(id: &str) -> String {
+    match id.strip_prefix("name:") {
+        Some(name) => format!("@{}", name),
+        None => id.to_string(),
+    }
+}
+
+/// Text as translators see it. Quotes are escaped in the script, but not in XLIFF.
+fn to_xliff_text(text: &str) -> String {
+    text.replace("\\\"", "\"")
+}
+
+/// Reverses `to_xliff_text()`.
+fn from_xliff_text(text: &str) -> String {
+    text.replace('"', "\\\"")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Text of element with all its descendants, `None` if there is no such element.
+fn child_text(node: roxmltree::Node<'_, '_>, name: &str) -> Option<String> {
+    let child = node
+        .children()
+        .find(|c| c.has_tag_name((XLIFF_NAMESPACE, name)))?;
+    Some(
+        child
+            .descendants()
+            .filter(|d| d.is_text())
+            .filter_map(|d| d.text())
+            .collect(),
+    )
+}
+
+impl StringTable {
+    /// Writes table as XLIFF 2.0 document. Targets are filled from `translation` if it's given.
+    /// Notes tell the speaker, label of the section and line of `path` the string is at.
+    pub fn to_xliff(
+        &self,
+        path: &str,
+        source_language: &str,
+        translation: Option<&StringTable>,
+    ) -> String {
+        let names: HashMap<&str, &str> = self
+            .entries
+            .iter()
+            .filter(|e| e.kind == StringKind::Name)
+            .rev()
+            .map(|e| (e.speakers[0].as_str(), e.text.as_str()))
+            .collect();
+        let mut result = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        result.push_str(&format!(
+            "<xliff xmlns=\"{}\" version=\"2.0\" srcLang=\"{}\"",
+            XLIFF_NAMESPACE,
+            escape(source_language)
+        ));
+        if let Some(language) = translation.and_then(|t| t.language.as_deref()) {
+            result.push_str(&format!(" trgLang=\"{}\"", escape(language)));
+        }
+        result.push_str(&format!(
+            ">\n  <file id=\"f1\" original=\"{}\">\n",
+            escape(path)
+        ));
+        for entry in &self.entries {
+            result.push_str(&format!(
+                "    <unit id=\"{}\">\n",
+                escape(&unit_id(&entry.key))
+            ));
+            let mut notes = Vec::new();
+            let speakers: Vec<String> = entry
+                .speakers
+                .iter()
+                .map(|h| match names.get(h.as_str()) {
+                    Some(name) => format!("{} (@{})", name, h),
+                    None => format!("@{}", h),
+                })
+                .collect();
+            match entry.kind {
+                StringKind::Phrase if !speakers.is_empty() => {
+                    notes.push(("speaker", speakers.join(" & ")))
+                }
+                StringKind::Phrase => notes.push(("speaker", String::from("(anonymous)"))),
+                StringKind::Choice => notes.push(("context", String::from("Choice option"))),
+                StringKind::Name => {
+                    notes.push(("context", format!("Name of @{}", entry.speakers[0])))
+                }
+            }
+            if let Some(label) = &entry.label {
+                notes.push(("label", label.clone()));
+            }
+            if let Some(line) = entry.line {
+                notes.push(("location", format!("{}:{}", path, line)));
+            }
+            result.push_str("      <notes>\n");
+            for (category, note) in notes {
+                result.push_str(&format!(
+                    "        <note category=\"{}\">{}</note>\n",
+                    category,
+                    escape(&note)
+                ));
+            }
+            result.push_str("      </notes>\n      <segment>\n");
+            result.push_str(&format!(
+                "        <source>{}</source>\n",
+                escape(&to_xliff_text(&entry.text))
+            ));
+            if let Some(target) = translation.and_then(|t| t.text(&entry.key)) {
+                result.push_str(&format!(
+                    "        <target>{}</target>\n",
+                    escape(&to_xliff_text(target))
+                ));
+            }
+            result.push_str("      </segment>\n    </unit>\n");
+        }
+        result.push_str("  </file>\n</xliff>\n");
+        result
+    }
+
+    /// Reads translated XLIFF 2.0 document. Every string of `source` table is looked up
+    /// by its unit, and keeps the source text if it has no target.
+    /// Units of strings which don't exist anymore, or have changed, are reported.
+    pub fn from_xliff(
+        src: &str,
+        source: &StringTable,
+    ) -> Result<(StringTable, XliffReport), XliffError> {
+        let (translation, units) = StringTable::parse_units(src)?;
+        let status = translation.status(source);
+        // Units without target are checked as well, unlike strings of the status.
+        let keys: HashSet<&str> = source.entries.iter().map(|e| e.key.as_str()).collect();
+        let report = XliffReport {
+            unknown: units
+                .iter()
+                .filter(|k| !keys.contains(k.as_str()))
+                .map(|k| unit_id(k))
+                .collect(),
+            stale: status.changed.iter().map(|k| unit_id(k)).collect(),
+            missing: status.new.iter().map(|k| unit_id(k)).collect(),
+        };
+        Ok((source.translated(&translation), report))
+    }
+
+    /// Reads units of XLIFF 2.0 document which have a target.
+    /// Source hash is taken from the unit source, which is the text it was translated from.
+    pub fn parse_xliff(src: &str) -> Result<StringTable, XliffError> {
+        Ok(StringTable::parse_units(src)?.0)
+    }
+
+    /// Reads units which have a target, and keys of every unit in the document.
+    fn parse_units(src: &str) -> Result<(StringTable, Vec<String>), XliffError> {
+        let document = roxmltree::Document::parse(src)?;
+        let root = document.root_element();
+        if !root.has_tag_name((XLIFF_NAMESPACE, "xliff")) {
+            return Err(XliffError::NotXliff);
+        }
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+        let mut entries = Vec::new();
+        for unit in root
+            .descendants()
+            .filter(|d| d.has_tag_name((XLIFF_NAMESPACE, "unit")))
+        {
+            let id = unit.attribute("id").ok_or_else(|| {
+                XliffError::MissingUnitId(document.text_pos_at(unit.range().start).row as usize)
+            })?;
+            let key = unit_key(id);
+            if !seen.insert(key.clone()) {
+                return Err(XliffError::DuplicateUnit(id.to_string()));
+            }
+            keys.push(key.clone());
+            let segments: Vec<_> = unit
+                .children()
+                .filter(|c| c.has_tag_name((XLIFF_NAMESPACE, "segment")))
+                .collect();
+            let join = |name| {
+                segments
+                    .iter()
+                    .map(|s| child_text(*s, name))
+                    .collect::<Option<String>>()
+                    .map(|t| from_xliff_text(&t))
+            };
+            let Some(text) = join("target").filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            entries.push(StringEntry {
+                kind: StringKind::of_key(&key),
+                key,
+                text,
+                speakers: Vec::new(),
+                label: None,
+                line: None,
+                source_hash: join("source").as_deref().map(source_hash),
+            });
+        }
+        let table = StringTable {
+            language: root.attribute("trgLang").map(str::to_string),
+            entries,
+        };
+        Ok((table, keys))
+    }
+}
@@ -0,0 +1,122 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod command {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use dialasm::{
+        Dialogue, DialogueEntry, Event, ParseError, ParseOptions, Runner, Value, ValueType,
+    };
+
+    #[test]
+    fn command_statement() {
+        const INPUT: &str = "do play_sound(\"door.ogg\", 0.5); do shake(); do give(-2, true);";
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(
+            dlg.entries(),
+            &[
+                DialogueEntry::Command(
+                    String::from("play_sound"),
+                    vec![Value::from("door.ogg"), Value::Number(0.5)]
+                ),
+                DialogueEntry::Command(String::from("shake"), vec![]),
+                DialogueEntry::Command(
+                    String::from("give"),
+                    vec![Value::Number(-2.0), Value::Bool(true)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn do_can_be_a_label() {
+        const INPUT: &str = "do: jump do;";
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(dlg.label("do"), Some(0));
+    }
+
+    #[test]
+    fn registered_commands_are_checked() {
+        let options = ParseOptions::new()
+            .command("play_sound", &[ValueType::String, ValueType::Number])
+            .command("shake", &[]);
+        assert!(Dialogue::parse_with("do play_sound(\"a.ogg\", 1); do shake();", &options).is_ok());
+        assert!(matches!(
+            Dialogue::parse_with("do jump();", &options),
+            Err(ParseError::UnknownCommand(n)) if n == "jump"
+        ));
+        assert!(matches!(
+            Dialogue::parse_with("do play_sound(\"a.ogg\");", &options),
+            Err(ParseError::CommandArity {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Dialogue::parse_with("do play_sound(\"a.ogg\", \"loud\");", &options),
+            Err(ParseError::CommandArgumentType {
+                index: 1,
+                expected: ValueType::Number,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn runner_dispatches_commands() {
+        let dlg = Dialogue::parse(
+            ": \"Knock knock.\"; do play_sound(\"door.ogg\", 0.5); : \"Who's there?\";",
+        )
+        .unwrap();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut runner = Runner::new(&dlg);
+        let log = calls.clone();
+        runner.set_command_handler(move |name: &str, arguments: &[Value]| {
+            log.borrow_mut()
+                .push((name.to_string(), arguments.to_vec()));
+        });
+        runner.advance();
+        assert!(calls.borrow().is_empty());
+        assert!(matches!(runner.advance(), Event::Line(l) if l.index == 2));
+        assert_eq!(
+            *calls.borrow(),
+            vec![(
+                String::from("play_sound"),
+                vec![Value::from("door.ogg"), Value::Number(0.5)]
+            )]
+        );
+    }
+
+    #[test]
+    fn clones_share_handler() {
+        let dlg = Dialogue::parse("do shake(); : \"Whoa!\"; do shake();").unwrap();
+        let calls = Rc::new(RefCell::new(0));
+        let mut runner = Runner::new(&dlg);
+        let count = calls.clone();
+        runner.set_command_handler(move |_: &str, _: &[Value]| *count.borrow_mut() += 1);
+        runner.advance();
+        let mut clone = runner.clone();
+        assert!(matches!(clone.advance(), Event::End));
+        assert_eq!(runner.pointer(), 1);
+        assert_eq!(*calls.borrow(), 2);
+    }
+
+    #[test]
+    fn entries_are_eq() {
+        fn assert_eq_bound<T: Eq>(_: &T) {}
+        let dlg = Dialogue::parse("do wait(0.5);").unwrap();
+        assert_eq_bound(&dlg.entries()[0]);
+        assert_eq!(Value::Number(f64::NAN), Value::Number(f64::NAN));
+        assert_eq!(Value::Number(0.0), Value::Number(-0.0));
+        assert_ne!(Value::Number(1.0), Value::from("1"));
+    }
+
+    #[test]
+    fn commands_without_handler_are_skipped() {
+        let dlg = Dialogue::parse("do shake(); : \"Whoa!\";").unwrap();
+        let mut runner = Runner::new(&dlg);
+        assert!(matches!(runner.advance(), Event::Line(l) if l.index == 1));
+    }
+}
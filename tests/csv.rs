@@ -0,0 +1,141 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod csv {
+    use super::*;
+    use dialasm::{CsvError, Dialogue};
+
+    const INPUT: &str = "/* Intro */
+@m = \"Maria\";
+@l = \"Leon\";
+start:
+@m:   \"Say \\\"hi\\\", please.\" #id:hi #mood:happy;
+(@m & @l): \"Together.\" #id:both;
+? (\"Left\": a | \"Right\": b) #id:way;
+a: : \"Line\nbreak\" #id:lines; /* keep */ : \"Done.\" #id:done;
+b:";
+
+    const EXPORT: &str = "id,label,speakers,text,target
+hi,start,@m,\"Say \"\"hi\"\", please.\",
+both,start,@m & @l,Together.,
+way.0,start,,Left,a
+way.1,start,,Right,b
+done,a,,Done.,
+";
+
+    fn edit(sheet: &str, from: &str, to: &str) -> String {
+        assert!(sheet.contains(from));
+        sheet.replacen(from, to, 1)
+    }
+
+    #[test]
+    fn export() {
+        let src = INPUT.replace(": \"Line\nbreak\" #id:lines; /* keep */ ", "");
+        let dlg = Dialogue::parse(&src).unwrap();
+        assert_eq!(dlg.to_csv(','), EXPORT);
+        assert_eq!(
+            dlg.to_csv('\t').lines().nth(1),
+            Some("hi\tstart\t@m\t\"Say \"\"hi\"\", please.\"\t")
+        );
+        let source = &src[dlg.meta(2).unwrap().ranges[0].clone()];
+        assert_eq!(source, "Say \\\"hi\\\", please.");
+    }
+
+    #[test]
+    fn ranges() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let ranges = &dlg.meta(4).unwrap().ranges;
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(&INPUT[ranges[1].clone()], "Right");
+        assert!(dlg.meta(0).unwrap().ranges.is_empty());
+    }
+
+    #[test]
+    fn unchanged() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let sheet = dlg.to_csv(',');
+        assert_eq!(Dialogue::apply_csv(INPUT, &sheet, ',').unwrap(), INPUT);
+        // Line breaks inside fields are kept as they are.
+        let sheet = dlg
+            .to_csv('\t')
+            .replace('\n', "\r\n")
+            .replace("Line\r\nbreak", "Line\nbreak");
+        assert_eq!(Dialogue::apply_csv(INPUT, &sheet, '\t').unwrap(), INPUT);
+    }
+
+    #[test]
+    fn text_edits() {
+        let sheet = Dialogue::parse(INPUT).unwrap().to_csv(',');
+        let sheet = edit(
+            &sheet,
+            "\"Say \"\"hi\"\", please.\"",
+            "\"Say \"\"hello\"\"!\"",
+        );
+        let sheet = edit(&sheet, ",Right,", ",\"Right, then\",");
+        let sheet = edit(&sheet, ",Done.,", ",All done.,");
+        let result = Dialogue::apply_csv(INPUT, &sheet, ',').unwrap();
+        assert_eq!(
+            result,
+            INPUT
+                .replace("Say \\\"hi\\\", please.", "Say \\\"hello\\\"!")
+                .replace("\"Right\"", "\"Right, then\"")
+                .replace("\"Done.\"", "\"All done.\"")
+        );
+        let dlg = Dialogue::parse(&result).unwrap();
+        assert_eq!(dlg.strings().text("way.1"), Some("Right, then"));
+    }
+
+    #[test]
+    fn structural_changes() {
+        let sheet = Dialogue::parse(INPUT).unwrap().to_csv(',');
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &edit(&sheet, "Right,b", "Right,a"), ','),
+            Err(CsvError::StructureChanged {
+                row: 5,
+                column: "target"
+            })
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &edit(&sheet, "@m & @l", "@l"), ','),
+            Err(CsvError::StructureChanged {
+                row: 3,
+                column: "speakers"
+            })
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &edit(&sheet, "hi,start", "hey,start"), ','),
+            Err(CsvError::StructureChanged {
+                row: 2,
+                column: "id"
+            })
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &format!("{}new,b,,New.,\n", sheet), ','),
+            Err(CsvError::RowCount {
+                expected: 6,
+                found: 7
+            })
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &edit(&sheet, "Left,a", "Left,a,"), ','),
+            Err(CsvError::ColumnCount { row: 4, found: 6 })
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &sheet.replacen("id,", "key,", 1), ','),
+            Err(CsvError::InvalidHeader)
+        ));
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, "id,label,speakers,text,target\n\"hi", ','),
+            Err(CsvError::UnterminatedField(2))
+        ));
+    }
+
+    #[test]
+    fn invalid_texts() {
+        let sheet = Dialogue::parse(INPUT).unwrap().to_csv(',');
+        assert!(matches!(
+            Dialogue::apply_csv(INPUT, &edit(&sheet, ",Done.,", ",Hi {@x},"), ','),
+            Err(CsvError::InvalidScript(_))
+        ));
+    }
+}
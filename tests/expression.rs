@@ -0,0 +1,203 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod expression {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use super::*;
+    use dialasm::{
+        Dialogue, DialogueEntry, Event, Expression, ExpressionError, FunctionRegistry, ParseError,
+        ParseOptions, RunError, Runner, Value, ValueType,
+    };
+
+    const INPUT: &str = "jump locked if !has_item(\"key\");
+        : \"The door opens.\"; jump end;
+        locked: : \"The door is locked.\";
+        jump end if $tries;
+        : \"Try again later.\";
+        end:";
+
+    fn functions<'a>(items: &'a [&'a str]) -> FunctionRegistry<'a> {
+        let mut functions = FunctionRegistry::new();
+        functions.register(
+            "has_item",
+            &[ValueType::String],
+            ValueType::Bool,
+            |args: &[Value]| Value::Bool(items.iter().any(|i| Value::from(*i) == args[0])),
+        );
+        functions.register("player_level", &[], ValueType::Number, |_: &[Value]| {
+            Value::Number(3.0)
+        });
+        functions
+    }
+
+    #[test]
+    fn conditional_jump() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(
+            dlg[0],
+            DialogueEntry::ConditionalJump(
+                String::from("locked"),
+                Expression::Not(Box::new(Expression::Call(
+                    String::from("has_item"),
+                    vec![Expression::Literal(Value::from("key"))]
+                )))
+            )
+        );
+        assert_eq!(
+            dlg[4],
+            DialogueEntry::ConditionalJump(
+                String::from("end"),
+                Expression::Variable(String::from("tries"))
+            )
+        );
+    }
+
+    #[test]
+    fn runner_calls_functions() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let items = ["key"];
+        let mut runner = Runner::new(&dlg);
+        runner.set_functions(functions(&items));
+        assert!(matches!(runner.advance(), Event::Line(l) if l.text == "The door opens."));
+        assert_eq!(runner.advance(), Event::End);
+
+        let mut runner = Runner::new(&dlg);
+        runner.set_functions(functions(&[]));
+        runner.set_variable("tries", true);
+        assert!(matches!(runner.advance(), Event::Line(l) if l.text == "The door is locked."));
+        assert_eq!(runner.advance(), Event::End);
+    }
+
+    #[test]
+    fn failed_conditions_are_false() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut runner = Runner::new(&dlg);
+        assert!(matches!(runner.advance(), Event::Line(l) if l.text == "The door opens."));
+    }
+
+    #[test]
+    fn failed_conditions_are_reported() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let errors = RefCell::new(Vec::new());
+        let mut runner = Runner::new(&dlg);
+        runner.set_error_handler(|e| errors.borrow_mut().push(e));
+        runner.advance();
+
+        let mut functions = FunctionRegistry::new();
+        functions.register("has_item", &[ValueType::String], ValueType::Bool, |_| {
+            Value::from("yes")
+        });
+        let mut runner = Runner::new(&dlg);
+        runner.set_error_handler(|e| errors.borrow_mut().push(e));
+        runner.set_functions(functions);
+        assert!(matches!(runner.advance(), Event::Line(l) if l.index == 1));
+
+        let mut runner = Runner::new(&dlg);
+        runner.set_error_handler(|e| errors.borrow_mut().push(e));
+        runner.set_variable("tries", 2);
+        runner.goto("locked").unwrap();
+        runner.advance();
+        assert!(matches!(runner.advance(), Event::Line(l) if l.index == 5));
+        assert_eq!(
+            *errors.borrow(),
+            vec![
+                RunError::Evaluation {
+                    index: 0,
+                    error: ExpressionError::UnknownFunction(String::from("has_item"))
+                },
+                RunError::Evaluation {
+                    index: 0,
+                    error: ExpressionError::ReturnType {
+                        name: String::from("has_item"),
+                        expected: ValueType::Bool,
+                        found: ValueType::String
+                    }
+                },
+                RunError::Evaluation {
+                    index: 4,
+                    error: ExpressionError::Type {
+                        expected: ValueType::Bool,
+                        found: ValueType::Number
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluation() {
+        let items = ["key"];
+        let functions = functions(&items);
+        let variables = HashMap::from([(String::from("gold"), Value::Number(5.0))]);
+        let call = Expression::Call(String::from("player_level"), vec![]);
+        assert_eq!(
+            call.evaluate(&variables, &functions),
+            Ok(Value::Number(3.0))
+        );
+        assert_eq!(
+            Expression::Variable(String::from("gold")).evaluate(&variables, &functions),
+            Ok(Value::Number(5.0))
+        );
+        assert_eq!(
+            Expression::Not(Box::new(Expression::Variable(String::from("gold"))))
+                .evaluate(&variables, &functions),
+            Err(ExpressionError::Type {
+                expected: ValueType::Bool,
+                found: ValueType::Number
+            })
+        );
+        assert_eq!(
+            Expression::Variable(String::from("silver")).evaluate(&variables, &functions),
+            Err(ExpressionError::UndefinedVariable(String::from("silver")))
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let items = [];
+        let functions = functions(&items);
+        let validate = |src: &str| Dialogue::parse(src).unwrap().validate_functions(&functions);
+        assert_eq!(validate(INPUT), Ok(()));
+        assert_eq!(
+            validate("a: jump a if unknown();"),
+            Err(ExpressionError::UnknownFunction(String::from("unknown")))
+        );
+        assert_eq!(
+            validate("a: jump a if has_item();"),
+            Err(ExpressionError::Arity {
+                name: String::from("has_item"),
+                expected: 1,
+                found: 0
+            })
+        );
+        assert_eq!(
+            validate("a: jump a if has_item(player_level());"),
+            Err(ExpressionError::ArgumentType {
+                name: String::from("has_item"),
+                index: 0,
+                expected: ValueType::String
+            })
+        );
+        assert_eq!(
+            validate("a: jump a if player_level();"),
+            Err(ExpressionError::Type {
+                expected: ValueType::Bool,
+                found: ValueType::Number
+            })
+        );
+        assert_eq!(validate("a: jump a if has_item($item);"), Ok(()));
+        let options = ParseOptions::new().functions(&functions);
+        assert!(Dialogue::parse_with(INPUT, &options).is_ok());
+        assert!(matches!(
+            Dialogue::parse_with("a: jump a if player_level();", &options),
+            Err(ParseError::InvalidExpression(ExpressionError::Type { .. }))
+        ));
+    }
+
+    #[test]
+    fn conditional_jumps_split_paths() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(dlg.paths(0, 1).len(), 3);
+    }
+}
@@ -0,0 +1,113 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod fountain {
+    use super::*;
+    use dialasm::Dialogue;
+
+    const INPUT: &str = "@m = \"Maria\";
+        @l = \"Leon\";
+        : \"The door opens.\";
+        start:
+        @m: \"Say \\\"hi\\\" to [b]{@l}[/b], [i]my_friend[/i]![wait=1]\" #mood:happy;
+        ? (\"[b]Hi[/b]\": a | \"Leave\": b);
+        a: @l = \"Leo\";
+        (@m & @l): \"Hello, {$name}.\";
+        do play_sound(\"door.ogg\", 0.5);
+        jump b if !has_key();
+        : \"THE END\";
+        b:";
+
+    #[test]
+    fn screenplay() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(
+            dlg.to_fountain(Some("Intro")),
+            "Title: Intro
+
+The door opens.
+
+.start
+
+MARIA
+(mood: happy)
+Say \"hi\" to **Leon**, *my\\_friend*!
+
+[[Choice:
+1. Hi → a
+2. Leave → b]]
+
+.a
+
+MARIA & LEO
+Hello, {$name}.
+
+[[do play_sound(\"door.ogg\", 0.5)]]
+
+[[Jump to b if !has_key()]]
+
+!THE END
+
+.b
+"
+        );
+    }
+
+    #[test]
+    fn forced_action() {
+        let texts = [
+            "int. kitchen is dark.",
+            "Ext garden",
+            "I/E car",
+            ".Dot",
+            "> Cut",
+            "# Part",
+            "= Summary",
+            "~ Lyrics",
+            "!Wow",
+            "[[[[Note]]",
+            "...and then.",
+            "Interesting.",
+        ];
+        let src: String = texts.iter().map(|t| format!(": \"{}\";", t)).collect();
+        let fountain = Dialogue::parse(&src).unwrap().to_fountain(None);
+        assert_eq!(
+            fountain.split("\n\n").collect::<Vec<_>>(),
+            vec![
+                "!int. kitchen is dark.",
+                "!Ext garden",
+                "!I/E car",
+                "!.Dot",
+                "!> Cut",
+                "!# Part",
+                "!= Summary",
+                "!~ Lyrics",
+                "!!Wow",
+                "![[Note]]",
+                "...and then.",
+                "Interesting.\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_markup() {
+        let dlg = Dialogue::parse(": \"[u][b]Bold [i]and[/i][/b] more[/u] done\";").unwrap();
+        assert_eq!(dlg.to_fountain(None), "_**Bold *and*** more_ done\n");
+    }
+
+    #[test]
+    fn without_title() {
+        let dlg = Dialogue::parse("@m = \"???\"; @m: \"Line\n\nafter blank line\";").unwrap();
+        assert_eq!(dlg.to_fountain(None), "@???\nLine\n  \nafter blank line\n");
+    }
+
+    #[test]
+    fn notes() {
+        let dlg = Dialogue::parse("? (\"a ]] b\": x | \"[[c]\": x); x: do log(\"]]]\");").unwrap();
+        assert_eq!(
+            dlg.to_fountain(None),
+            "[[Choice:\n1. a ] ] b → x\n2. [c] → x]]\n\n.x\n\n[[do log(\"] ] ]\")]]\n"
+        );
+    }
+}
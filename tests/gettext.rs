@@ -0,0 +1,190 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod gettext {
+    use super::*;
+    use dialasm::{Dialogue, Event, PoError, Runner, StringTable, source_hash};
+
+    const INPUT: &str = "@m = \"Maria\";
+        @m: \"Say \\\"hi\\\"\" #id:hi;
+        ? (\"Left\": a | \"Right\": a) #id:way;
+        a:";
+
+    const TEMPLATE: &str = r#"msgid ""
+msgstr ""
+"MIME-Version: 1.0\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Content-Transfer-Encoding: 8bit\n"
+
+#. Name of @m
+#: intro.dlg:1
+msgctxt "@m"
+msgid "Maria"
+msgstr ""
+
+#. @m
+#: intro.dlg:2
+msgctxt "hi"
+msgid "Say \"hi\""
+msgstr ""
+
+#. Choice option
+#: intro.dlg:3
+msgctxt "way.0"
+msgid "Left"
+msgstr ""
+
+#. Choice option
+#: intro.dlg:3
+msgctxt "way.1"
+msgid "Right"
+msgstr ""
+"#;
+
+    const TRANSLATION: &str = r#"# German translation
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Language: de\n"
+
+#: intro.dlg:2
+msgctxt "hi"
+msgid "Say \"hi\""
+msgstr ""
+"Sag \"hallo\""
+
+#, fuzzy
+msgctxt "way.0"
+msgid "Left"
+msgstr "Rechts"
+
+msgctxt "way.1"
+msgid "Right"
+msgstr ""
+
+msgctxt "removed"
+msgid "Removed line"
+msgstr "Entfernte Zeile"
+"#;
+
+    #[test]
+    fn export_template() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(dlg.strings().to_pot("intro.dlg"), TEMPLATE);
+    }
+
+    #[test]
+    fn import_translation() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let table = StringTable::from_po(TRANSLATION, &dlg.strings())
+            .expect("Expected to parse valid PO file");
+        assert_eq!(table.language.as_deref(), Some("de"));
+        assert_eq!(
+            table
+                .entries
+                .iter()
+                .map(|e| (e.key.as_str(), e.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("@m", "Maria"),
+                ("hi", "Sag \\\"hallo\\\""),
+                ("way.0", "Left"),
+                ("way.1", "Right"),
+            ]
+        );
+        let mut runner = Runner::new(&dlg);
+        runner.set_translation(&table);
+        assert!(matches!(runner.advance(), Event::Line(l) if l.text == "Sag \\\"hallo\\\""));
+    }
+
+    #[test]
+    fn template_round_trip() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let translated = TEMPLATE.replace("msgstr \"\"\n\n", "msgstr \"X\"\n\n");
+        let table = StringTable::from_po(&translated, &dlg.strings()).unwrap();
+        assert_eq!(table.text("@m"), Some("X"));
+        assert_eq!(table.text("way.1"), Some("Right"));
+    }
+
+    #[test]
+    fn plural_forms() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let po = r#"msgctxt "way.0"
+msgid "Left"
+msgid_plural ""
+"Lefts"
+msgstr[0] ""
+"Links"
+msgstr[1] ""
+"Linke"
+"#;
+        let table = StringTable::from_po(po, &dlg.strings()).unwrap();
+        assert_eq!(table.text("way.0"), Some("Links"));
+    }
+
+    #[test]
+    fn adjacent_entries() {
+        let po = r#"msgctxt "a"
+msgid "Hello"
+msgstr "Hallo"
+msgctxt "b"
+msgid "Bye"
+msgstr "Tschuss"
+msgctxt "c"
+msgid "Yes"
+msgstr ""
+#, fuzzy
+msgctxt "d"
+msgid "No"
+msgstr "Nein"
+msgctxt "e"
+msgid "Maybe"
+msgstr "Vielleicht"
+"#;
+        let table = StringTable::parse_po(po).unwrap();
+        let texts: Vec<(&str, &str)> = table
+            .entries
+            .iter()
+            .map(|e| (e.key.as_str(), e.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            [("a", "Hallo"), ("b", "Tschuss"), ("e", "Vielleicht")]
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let mut table = Dialogue::parse(INPUT).unwrap().strings();
+        table.entries[1].text = String::from("Raw \"quote\" and \\\"escaped\\\" one\\");
+        let pot = table.to_pot("intro.dlg");
+        assert!(pot.contains(
+            r#"msgid "Raw \"quote\" and \"escaped\" one\\"
+"#
+        ));
+        let translated = pot.replace("msgstr \"\"\n\n", "msgstr \"X\"\n\n");
+        let parsed = StringTable::parse_po(&translated).unwrap();
+        // Quotes and backslashes come back escaped, as in the script.
+        assert_eq!(
+            parsed.get("hi").unwrap().source_hash,
+            Some(source_hash("Raw \\\"quote\\\" and \\\"escaped\\\" one\\\\"))
+        );
+    }
+
+    #[test]
+    fn invalid_po() {
+        let source = StringTable::default();
+        assert_eq!(
+            StringTable::from_po("msgid \"a\"\nmsgstr \"b", &source),
+            Err(PoError::UnterminatedString(2))
+        );
+        assert_eq!(
+            StringTable::from_po("\"a\"", &source),
+            Err(PoError::InvalidSyntax(1))
+        );
+        assert_eq!(
+            StringTable::from_po("msgfoo \"a\"", &source),
+            Err(PoError::InvalidSyntax(1))
+        );
+    }
+}
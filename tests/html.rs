@@ -0,0 +1,99 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod html {
+    use super::*;
+    use dialasm::{Dialogue, TranscriptTest};
+    use std::fs;
+
+    /// Data embedded into the page.
+    fn data(html: &str) -> serde_json::Value {
+        let start = html.find("type=\"application/json\">").unwrap() + 24;
+        let end = start + html[start..].find("</script>").unwrap();
+        serde_json::from_str(&html[start..end]).unwrap()
+    }
+
+    #[test]
+    fn self_contained() {
+        let html = Dialogue::example().to_html("Example");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Example</title>"));
+        assert!(!html.contains("src="));
+        assert!(!html.contains("href="));
+        let data = data(&html);
+        assert_eq!(data["entries"].as_array().unwrap().len(), 14);
+        assert_eq!(data["labels"]["end"], 13);
+    }
+
+    #[test]
+    fn entries() {
+        let dlg = Dialogue::parse(
+            "@m = \"Maria\" { color: \"red\" };
+            @m: \"Hi {@m}, you have {$gold}.\" #id:hi;
+            ? (\"Go\": a | \"Stay\": b) #id:way;
+            a: jump b if !has_key(\"door\");
+            do play(1);
+            b:",
+        )
+        .unwrap();
+        let data = data(&dlg.to_html("Test"));
+        let entries = data["entries"].as_array().unwrap();
+        assert_eq!(
+            entries[0],
+            serde_json::json!({ "kind": "name", "handle": "m", "name": "Maria" })
+        );
+        assert_eq!(entries[1]["kind"], "attributes");
+        assert_eq!(
+            entries[2],
+            serde_json::json!({
+                "kind": "phrase",
+                "id": "hi",
+                "speakers": ["m"],
+                "text": [
+                    { "text": "Hi " },
+                    { "speaker": "m" },
+                    { "text": ", you have " },
+                    { "expression": { "variable": "gold" }, "source": "$gold" },
+                    { "text": "." },
+                ],
+            })
+        );
+        assert_eq!(entries[3]["options"][1]["label"], "b");
+        assert_eq!(entries[3]["options"][1]["text"][0]["text"], "Stay");
+        assert_eq!(
+            entries[4]["condition"],
+            serde_json::json!({ "not": { "call": "has_key", "arguments": [{ "literal": "door" }] } })
+        );
+        assert_eq!(entries[5]["kind"], "command");
+    }
+
+    #[test]
+    fn escaping() {
+        let dlg = Dialogue::parse(": \"</script><b>{{{{title}}}}\";").unwrap();
+        let html = dlg.to_html("<Intro & {{dialogue}}>");
+        assert!(html.contains("<title>&lt;Intro &amp; {{dialogue}}&gt;</title>"));
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert_eq!(
+            data(&html)["entries"][0]["text"][0]["text"],
+            "</script><b>{{title}}"
+        );
+    }
+
+    /// The golden playthroughs in `tests/player` are played in the page by
+    /// `make player-test`, so the runner has to give the same transcripts.
+    #[test]
+    fn player_playthroughs() {
+        let dlg = Dialogue::parse(include_str!("player/player.dlg")).unwrap();
+        let mut count = 0;
+        for file in fs::read_dir("tests/player").unwrap() {
+            let path = file.unwrap().path();
+            if path.extension().is_some_and(|e| e == "dlgtest") {
+                let test = TranscriptTest::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+                assert_eq!(test.script, "player.dlg");
+                assert_eq!(test.run(&dlg).unwrap(), None, "{}", path.display());
+                count += 1;
+            }
+        }
+        assert_eq!(count, 5);
+    }
+}
@@ -0,0 +1,105 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod ids {
+    use super::*;
+    use dialasm::{Dialogue, Event, ParseError, RunError, Runner};
+
+    const INPUT: &str = "@m = \"Maria\";
+        @m: \"Hello!\" #id:hello;
+        @m: \"How are you?\";
+        ? (\"Fine\": fine | \"Bad\": bad);
+        fine: @m: \"Good.\"; jump end;
+        bad: @m: \"Oh.\";
+        end: @m: \"Bye!\";";
+
+    #[test]
+    fn explicit_and_generated_ids() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(dlg.id(0), None);
+        assert_eq!(dlg.id(1), Some("hello"));
+        assert_eq!(dlg.find_id("hello"), Some(1));
+        let generated = dlg.id(2).unwrap();
+        assert_eq!(generated.len(), 8);
+        assert!(dlg.id(3).is_some());
+        assert!(dlg.id(4).unwrap().starts_with("fine-"));
+        assert_eq!(dlg.id(5), None);
+        assert!(dlg.id(7).unwrap().starts_with("end-"));
+    }
+
+    #[test]
+    fn generated_ids_survive_insertions() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let edited = Dialogue::parse(&INPUT.replace(
+            "fine: @m: \"Good.\";",
+            "fine: @m: \"Nice.\"; @m: \"Good.\";",
+        ))
+        .unwrap();
+        assert_eq!(dlg.id(4), edited.id(5));
+        assert_eq!(dlg.id(7), edited.id(8));
+        assert_eq!(dlg.id(2), edited.id(2));
+    }
+
+    #[test]
+    fn identical_lines_get_different_ids() {
+        let dlg = Dialogue::parse(": \"...\"; : \"...\";").unwrap();
+        assert_eq!(format!("{}-2", dlg.id(0).unwrap()), dlg.id(1).unwrap());
+    }
+
+    #[test]
+    fn duplicate_explicit_id() {
+        let result = Dialogue::parse(": \"A\" #id:a; : \"B\" #id:a;");
+        assert!(matches!(result, Err(ParseError::DuplicateId(id)) if id == "a"));
+    }
+
+    #[test]
+    fn anchors_are_resolved_in_edited_dialogue() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let edited = Dialogue::parse(
+            &INPUT.replace("bad: @m: \"Oh.\";", "bad: @m: \"Hmm.\"; @m: \"Oh no.\";"),
+        )
+        .unwrap();
+        // Same id.
+        assert_eq!(edited.resolve_anchor(&dlg.anchor(7)), Some(8));
+        // Id is gone, same offset in the section is used.
+        assert_eq!(edited.resolve_anchor(&dlg.anchor(6)), Some(6));
+        // Jump before the phrase.
+        assert_eq!(edited.resolve_anchor(&dlg.anchor(5)), Some(5));
+    }
+
+    #[test]
+    fn runner_state_is_remapped() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut runner = Runner::new(&dlg);
+        runner.goto("end").unwrap();
+        runner.advance();
+        let state = runner.state();
+
+        let edited = Dialogue::parse(&INPUT.replace("@m: \"Hello!\"", "@m: \"Hi!\"")).unwrap();
+        let mut runner = Runner::new(&edited);
+        assert!(runner.load_state(state.clone()).is_err());
+        runner.load_state_remapped(state).unwrap();
+        let Event::Line(line) = runner.resume() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.text, "Bye!");
+        assert_eq!(runner.advance(), Event::End);
+    }
+
+    #[test]
+    fn unresolved_anchor() {
+        let dlg = Dialogue::parse("start: : \"Hello!\" #id:hello; : \"Bye!\";").unwrap();
+        let mut runner = Runner::new(&dlg);
+        runner.advance();
+        let state = runner.state();
+
+        let edited = Dialogue::parse("intro: : \"Hi!\" #id:hi;").unwrap();
+        assert_eq!(
+            Runner::new(&edited).load_state_remapped(state),
+            Err(RunError::UnresolvedAnchor {
+                id: Some(String::from("hello")),
+                label: Some(String::from("start")),
+            })
+        );
+    }
+}
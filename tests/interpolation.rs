@@ -0,0 +1,131 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod interpolation {
+    use super::*;
+    use dialasm::{
+        Dialogue, Event, Expression, ExpressionError, FunctionRegistry, ParseError, Runner,
+        TextPart, Value, ValueType,
+    };
+
+    const INPUT: &str = "@m = \"Maria\";
+        @m: \"Hello, {$player_name}! You have {$gold} gold.\";
+        @m: \"I am {@m}. Sets look like {{1, 2}}.\";
+        ? (\"Pay {price(\\\"sword\\\")} gold\": a | \"Leave\": a);
+        a: @m = \"Mary\";
+        : \"Call me {@m}, {$player_name}.\";";
+
+    #[test]
+    fn texts() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(
+            dlg.meta(1).unwrap().texts,
+            vec![vec![
+                TextPart::Text(String::from("Hello, ")),
+                TextPart::Expression(Expression::Variable(String::from("player_name"))),
+                TextPart::Text(String::from("! You have ")),
+                TextPart::Expression(Expression::Variable(String::from("gold"))),
+                TextPart::Text(String::from(" gold.")),
+            ]]
+        );
+        assert_eq!(
+            dlg.meta(2).unwrap().texts,
+            vec![vec![
+                TextPart::Text(String::from("I am ")),
+                TextPart::Speaker(String::from("m")),
+                TextPart::Text(String::from(". Sets look like {1, 2}.")),
+            ]]
+        );
+        assert_eq!(
+            dlg.meta(3).unwrap().texts,
+            vec![
+                vec![
+                    TextPart::Text(String::from("Pay ")),
+                    TextPart::Expression(Expression::Call(
+                        String::from("price"),
+                        vec![Expression::Literal(Value::from("sword"))]
+                    )),
+                    TextPart::Text(String::from(" gold")),
+                ],
+                vec![TextPart::Text(String::from("Leave"))],
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_interpolation() {
+        for src in [
+            ": \"Unclosed {$gold\";",
+            ": \"Unopened $gold}\";",
+            ": \"Empty {}\";",
+            ": \"Not an expression {gold + 1}\";",
+            "? \"Pay {$}\": a; a:",
+        ] {
+            assert!(
+                matches!(
+                    Dialogue::parse(src),
+                    Err(ParseError::InvalidInterpolation(_))
+                ),
+                "Expected interpolation error in {}",
+                src
+            );
+        }
+        assert!(matches!(
+            Dialogue::parse(": \"I am {@m}\";"),
+            Err(ParseError::UndefinedSpeaker(h)) if h == "m"
+        ));
+    }
+
+    #[test]
+    fn runner_renders_text() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut functions = FunctionRegistry::new();
+        functions.register("price", &[ValueType::String], ValueType::Number, |_| {
+            Value::Number(30.0)
+        });
+        let mut runner = Runner::new(&dlg);
+        runner.set_functions(functions);
+        runner.set_variable("player_name", "Alex");
+        runner.set_variable("gold", 12);
+        let text = |e: Event| match e {
+            Event::Line(l) => l.text,
+            e => panic!("Expected line, got {:?}", e),
+        };
+        assert_eq!(text(runner.advance()), "Hello, Alex! You have 12 gold.");
+        assert_eq!(text(runner.advance()), "I am Maria. Sets look like {1, 2}.");
+        match runner.advance() {
+            Event::Choices(c) => assert_eq!(c.options, vec!["Pay 30 gold", "Leave"]),
+            e => panic!("Expected choices, got {:?}", e),
+        }
+        runner.choose(0).unwrap();
+        runner.set_variable("gold", 0);
+        assert_eq!(text(runner.advance()), "Call me Mary, Alex.");
+        assert_eq!(
+            dlg[5],
+            dialasm::DialogueEntry::Phrase(
+                Vec::new(),
+                String::from("Call me {@m}, {$player_name}.")
+            )
+        );
+    }
+
+    #[test]
+    fn failed_interpolation_is_shown_as_written() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut runner = Runner::new(&dlg);
+        runner.set_variable("player_name", "Alex");
+        match runner.advance() {
+            Event::Line(l) => assert_eq!(l.text, "Hello, Alex! You have {$gold} gold."),
+            e => panic!("Expected line, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn validation() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(
+            dlg.validate_functions(&FunctionRegistry::new()),
+            Err(ExpressionError::UnknownFunction(String::from("price")))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod l10n {
+    use super::*;
+    use dialasm::{Dialogue, Event, Runner, StringEntry, StringKind, StringTable, source_hash};
+
+    const INPUT: &str = "@m = \"Maria\";
+        @m: \"Hello, I am {@m}!\" #id:hello;
+        ? (\"Left\": a | \"Right\": b) #id:way;
+        a: @m = \"Mary\";
+        @m: \"Left it is.\" #id:left;
+        jump end;
+        b: @m = \"Maria\";
+        @m: \"Right it is.\" #id:right;
+        end:";
+
+    fn translate(dlg: &Dialogue, texts: &[(&str, &str)]) -> StringTable {
+        let mut table = dlg.strings();
+        table.language = Some(String::from("de"));
+        for (key, text) in texts {
+            table
+                .entries
+                .iter_mut()
+                .find(|e| e.key == *key)
+                .unwrap()
+                .text = text.to_string();
+        }
+        table
+    }
+
+    #[test]
+    fn strings() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        let table = dlg.strings();
+        assert_eq!(
+            table
+                .entries
+                .iter()
+                .map(|e| (e.key.as_str(), e.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("@m", "Maria"),
+                ("hello", "Hello, I am {@m}!"),
+                ("way.0", "Left"),
+                ("way.1", "Right"),
+                ("@m-2", "Mary"),
+                ("left", "Left it is."),
+                ("right", "Right it is."),
+            ]
+        );
+        assert_eq!(
+            table.get("left"),
+            Some(&StringEntry {
+                key: String::from("left"),
+                kind: StringKind::Phrase,
+                text: String::from("Left it is."),
+                speakers: vec![String::from("m")],
+                label: Some(String::from("a")),
+                line: Some(5),
+                source_hash: Some(source_hash("Left it is.")),
+            })
+        );
+        assert_eq!(table.get("@m-2").unwrap().kind, StringKind::Name);
+        assert_eq!(table.get("way.1").unwrap().line, Some(3));
+        assert_eq!(dlg.text_key(2, 1), Some(String::from("way.1")));
+        assert_eq!(dlg.text_key(2, 2), None);
+    }
+
+    #[test]
+    fn serialization() {
+        let table = Dialogue::parse(INPUT).unwrap().strings();
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.starts_with(
+            r#"{"entries":[{"key":"@m","kind":"name","text":"Maria","speakers":["m"],"line":1,"source_hash":"e6e001ae"}"#
+        ));
+        assert_eq!(serde_json::from_str::<StringTable>(&json).unwrap(), table);
+    }
+
+    #[test]
+    fn runner_translation() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let table = translate(
+            &dlg,
+            &[
+                ("@m", "Marie"),
+                ("hello", "Hallo, ich bin {@m}!"),
+                ("way.0", "Links"),
+                ("left", "Also links."),
+            ],
+        );
+        let mut runner = Runner::new(&dlg);
+        runner.set_translation(&table);
+        match runner.advance() {
+            Event::Line(l) => {
+                assert_eq!(l.names, vec!["Marie"]);
+                assert_eq!(l.text, "Hallo, ich bin Marie!");
+            }
+            e => panic!("Expected line, got {:?}", e),
+        }
+        match runner.advance() {
+            Event::Choices(c) => assert_eq!(c.options, vec!["Links", "Right"]),
+            e => panic!("Expected choices, got {:?}", e),
+        }
+        runner.choose(0).unwrap();
+        match runner.advance() {
+            Event::Line(l) => {
+                assert_eq!(l.names, vec!["Mary"]);
+                assert_eq!(l.text, "Also links.");
+            }
+            e => panic!("Expected line, got {:?}", e),
+        }
+        assert_eq!(runner.speakers().get("m").unwrap(), "Mary");
+        assert_eq!(runner.advance(), Event::End);
+    }
+
+    #[test]
+    fn invalid_translation_is_ignored() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let table = translate(&dlg, &[("hello", "Hallo, {@m"), ("right", "[b]Rechts")]);
+        let mut runner = Runner::new(&dlg);
+        runner.set_translation(&table);
+        assert_eq!(
+            runner.advance(),
+            Runner::new(&dlg).advance(),
+            "Expected source text"
+        );
+        runner.advance();
+        runner.choose(1).unwrap();
+        assert!(matches!(runner.advance(), Event::Line(l) if l.text == "Right it is."));
+    }
+}
@@ -0,0 +1,114 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod lint {
+    use super::*;
+    use dialasm::{AudioReport, Dialogue, LintIssue, LintOptions};
+    use std::collections::BTreeMap;
+
+    const INPUT: &str = "@m = \"Maria\";
+        @l = \"Leon\";
+        start:
+        @m: \"Hi.\" #id:hi;
+        (@m & @l): \"Together.\" #id:both;
+        jump end;
+        @m: \"Never said.\" #id:dead;
+        : \"Neither is this.\";
+        other:
+        @l: \"Only from the host.\" #id:other;
+        end:";
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn unreachable() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(
+            dlg.unreachable().into_iter().collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+        assert!(dlg.lint(&LintOptions::new()).is_empty());
+        assert_eq!(
+            dlg.lint(&LintOptions::new().unreachable()),
+            vec![LintIssue {
+                rule: "unreachable",
+                message: String::from("2 statements are never reached"),
+                line: Some(7),
+            }]
+        );
+        let dlg = Dialogue::parse("? (\"A\": a | \"B\": b); a: jump a; b:").unwrap();
+        assert!(dlg.lint(&LintOptions::new().unreachable()).is_empty());
+    }
+
+    #[test]
+    fn check_audio() {
+        let script = Dialogue::parse(INPUT).unwrap().vo_script("wav");
+        let report = script.check_audio(&files(&[
+            "m/hi.wav",
+            "m/dead.wav",
+            "l/both.WAV",
+            "l/both.ogg",
+            "l/other.wav",
+            "m/removed.wav",
+            "m/notes.txt",
+            "manifest.csv",
+        ]));
+        assert_eq!(
+            report,
+            AudioReport {
+                missing: vec![String::from("m/both.wav")],
+                orphaned: vec![String::from("m/removed.wav")],
+                duplicates: BTreeMap::from([(
+                    String::from("l/both.wav"),
+                    files(&["l/both.WAV", "l/both.ogg"])
+                )]),
+            }
+        );
+        assert!(!report.is_empty());
+        let complete = files(&[
+            "l/both.ogg",
+            "l/other.ogg",
+            "m/both.ogg",
+            "m/dead.ogg",
+            "m/hi.ogg",
+        ]);
+        assert!(script.check_audio(&complete).is_empty());
+    }
+
+    #[test]
+    fn audio_rule() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let options = LintOptions::new().unreachable().audio(
+            &files(&["m/hi.wav", "l/both.wav", "l/other.wav", "l/old.wav"]),
+            "wav",
+        );
+        let issues: Vec<(Option<usize>, String)> = dlg
+            .lint(&options)
+            .into_iter()
+            .map(|i| (i.line, format!("[{}] {}", i.rule, i.message)))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    Some(5),
+                    String::from("[audio] Recording 'm/both.wav' is missing")
+                ),
+                (
+                    Some(7),
+                    String::from("[unreachable] 2 statements are never reached")
+                ),
+                (
+                    Some(7),
+                    String::from("[audio] Recording 'm/dead.wav' is missing")
+                ),
+                (
+                    None,
+                    String::from("[audio] Recording 'l/old.wav' doesn't belong to any phrase")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod markdown {
+    use super::*;
+    use dialasm::Dialogue;
+
+    #[test]
+    fn document() {
+        let dlg = Dialogue::parse(
+            "@m = \"Maria\";
+            @l = \"Leon\";
+            : \"The door opens.\";
+            start:
+            @m: \"Say \\\"hi\\\" to [b]{@l}[/b], [i]my_friend[/i]![wait=1]\" #mood:happy;
+            ? (\"[b]Hi[/b]\": a | \"Leave\": Back);
+            a: @l = \"Leo\";
+            (@m & @l): \"Hello, {$name}.\";
+            do play_sound(\"door.ogg\", 0.5);
+            jump Back if !has_key();
+            jump a;
+            Back:",
+        )
+        .unwrap();
+        assert_eq!(
+            dlg.to_markdown(Some("Intro")),
+            "# Intro
+
+The door opens.
+
+## start
+
+**Maria:** Say \"hi\" to **Leon**, *my\\_friend*! *(mood: happy)*
+
+1. Hi → [a](#a)
+2. Leave → [Back](#back)
+
+## a
+
+**Maria & Leo:** Hello, {$name}.
+
+`do play_sound(\"door.ogg\", 0.5)`
+
+→ [Back](#back) if `!has_key()`
+
+→ [a](#a)
+
+## Back
+"
+        );
+    }
+
+    #[test]
+    fn nested_markup() {
+        let dlg = Dialogue::parse(
+            ": \"[b]a[i]b[/i][/b], [i]c [b]d[/b][/i] [b]e [/b]f [u][b]g[/b][/u]\";",
+        )
+        .unwrap();
+        assert_eq!(dlg.to_markdown(None), "**a*b***, *c **d*** **e** f **g**\n");
+    }
+
+    #[test]
+    fn escaping() {
+        let dlg = Dialogue::parse("@m = \"<M>\"; @m: \"- one\n\n# [[two] `3`\";").unwrap();
+        assert_eq!(
+            dlg.to_markdown(None),
+            "**\\<M\\>:** \\- one\\\n\\\n\\# \\[two\\] \\`3\\`\n"
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        let dlg = Dialogue::parse(
+            "intro: jump Start;
+            Start: jump start;
+            start: jump intro;",
+        )
+        .unwrap();
+        assert_eq!(
+            dlg.to_markdown(Some("intro")),
+            "# intro
+
+## intro
+
+→ [Start](#start)
+
+## Start
+
+→ [start](#start-1)
+
+## start
+
+→ [intro](#intro-1)
+"
+        );
+    }
+
+    #[test]
+    fn block_markers() {
+        let dlg =
+            Dialogue::parse(": \"1984. It was cold.\"; : \"Title\n===\n---\n 2) two\";").unwrap();
+        assert_eq!(
+            dlg.to_markdown(None),
+            "1984\\. It was cold.\n\nTitle\\\n\\===\\\n\\---\\\n 2\\) two\n"
+        );
+    }
+}
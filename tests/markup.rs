@@ -0,0 +1,138 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod markup {
+    use super::*;
+    use dialasm::{
+        Dialogue, Event, MarkupError, ParseError, Runner, Span, Tag, parse_markup, plain_text,
+        protocol,
+    };
+
+    fn tag(name: &str, value: Option<&str>) -> Tag {
+        Tag {
+            name: name.to_string(),
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn text(text: &str, tags: &[Tag]) -> Span {
+        Span::Text {
+            text: text.to_string(),
+            tags: tags.to_vec(),
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let spans = parse_markup("I [b]really [color=red]mean[/color][/b] it.[wait=0.5] [[Sighs]")
+            .expect("Expected to parse valid markup");
+        let b = tag("b", None);
+        let red = tag("color", Some("red"));
+        assert_eq!(
+            spans,
+            vec![
+                text("I ", &[]),
+                text("really ", std::slice::from_ref(&b)),
+                text("mean", &[b, red]),
+                text(" it.", &[]),
+                Span::Marker(tag("wait", Some("0.5"))),
+                text(" [Sighs]", &[]),
+            ]
+        );
+        assert_eq!(plain_text(&spans), "I really mean it. [Sighs]");
+        assert_eq!(
+            parse_markup("Plain text"),
+            Ok(vec![text("Plain text", &[])])
+        );
+    }
+
+    #[test]
+    fn invalid_markup() {
+        assert_eq!(
+            parse_markup("[b]Bold"),
+            Err(MarkupError::UnclosedTag(String::from("b")))
+        );
+        assert_eq!(
+            parse_markup("[b][i]Bold[/b][/i]"),
+            Err(MarkupError::UnexpectedClosingTag(String::from("b")))
+        );
+        assert_eq!(
+            parse_markup("Hi[/wait]"),
+            Err(MarkupError::UnexpectedClosingTag(String::from("wait")))
+        );
+        assert_eq!(
+            parse_markup("[1] Footnote"),
+            Err(MarkupError::InvalidTag(String::from("[1]")))
+        );
+        assert!(matches!(
+            Dialogue::parse("? \"[i]Lie\": a; a:"),
+            Err(ParseError::InvalidMarkup(_, MarkupError::UnclosedTag(_)))
+        ));
+    }
+
+    #[test]
+    fn dialogue_spans() {
+        let dlg = Dialogue::parse(
+            "@m = \"Maria\";
+            @m: \"[b]Hi, {@m}![/b] Take {price(\\\"[sword]\\\")}.\";
+            ? (\"[i]Yes[/i]\": a | \"No\": a);
+            a:",
+        )
+        .unwrap();
+        let b = tag("b", None);
+        assert_eq!(
+            dlg.meta(1).unwrap().spans,
+            vec![vec![
+                text("Hi, {@m}!", &[b]),
+                text(" Take {price(\"[sword]\")}.", &[]),
+            ]]
+        );
+        assert_eq!(
+            dlg.meta(2).unwrap().spans,
+            vec![vec![text("Yes", &[tag("i", None)])], vec![text("No", &[])]]
+        );
+        assert!(dlg.meta(0).unwrap().spans.is_empty());
+    }
+
+    #[test]
+    fn runner_lines() {
+        let dlg = Dialogue::parse(
+            "@m = \"[Maria]\";
+            @m: \"[speed=2]Hello, {@m}![/speed] You have {$items}.\";
+            ? (\"[b]Yes[/b]\": a | \"No\": a);
+            a: : \"Plain\";",
+        )
+        .unwrap();
+        let mut runner = Runner::new(&dlg);
+        runner.set_variable("items", "[b]");
+        match runner.advance() {
+            Event::Line(l) => {
+                assert_eq!(l.text, "Hello, [Maria]! You have [b].");
+                assert_eq!(
+                    l.spans,
+                    vec![
+                        text("Hello, [Maria]!", &[tag("speed", Some("2"))]),
+                        text(" You have [b].", &[]),
+                    ]
+                );
+            }
+            e => panic!("Expected line, got {:?}", e),
+        }
+        match runner.advance() {
+            Event::Choices(c) => {
+                assert_eq!(c.options, vec!["Yes", "No"]);
+                assert_eq!(
+                    c.spans,
+                    vec![vec![text("Yes", &[tag("b", None)])], vec![text("No", &[])]]
+                );
+            }
+            e => panic!("Expected choices, got {:?}", e),
+        }
+        runner.choose(1).unwrap();
+        assert_eq!(
+            serde_json::to_string(&protocol::execute(&mut runner, protocol::Command::Advance))
+                .unwrap(),
+            r#"{"event":"line","index":3,"id":"a-fb36869c","handles":[],"names":[],"text":"Plain"}"#
+        );
+    }
+}
@@ -0,0 +1,77 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod paths {
+    use super::*;
+    use dialasm::{CoverageError, Dialogue, count_endings};
+
+    #[test]
+    fn example_dialogue_paths() {
+        let dlg = Dialogue::example();
+        let paths = dlg.paths(0, 1);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].choices, vec![0, 0]);
+        assert_eq!(
+            paths[0].entries,
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 12, 13]
+        );
+        assert_eq!(paths[1].choices, vec![1, 0]);
+        assert_eq!(
+            paths[1].entries,
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 10, 11, 12, 13]
+        );
+        assert!(paths.iter().all(|p| p.complete));
+        assert_eq!(count_endings(&paths), 1);
+    }
+
+    #[test]
+    fn paths_from_label() {
+        let dlg = Dialogue::example();
+        let paths = dlg.paths_from("b", 1).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].entries, vec![10, 11, 12, 13]);
+        assert!(dlg.paths_from("nowhere", 1).is_none());
+    }
+
+    #[test]
+    fn loops_are_bounded() {
+        const INPUT: &str =
+            "start: : \"Again?\"; ? (\"Yes\": start | \"No\": end); end: : \"Bye\";";
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let paths = dlg.paths(0, 2);
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths.iter().filter(|p| !p.complete).count(), 1);
+        assert_eq!(count_endings(&paths), 1);
+    }
+
+    #[test]
+    fn multiple_endings() {
+        const INPUT: &str = "? (\"A\": a | \"B\": b); a: : \"A\"; jump end; b: : \"B\"; end:";
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let paths = dlg.paths(0, 1);
+        assert_eq!(count_endings(&paths), 2);
+    }
+
+    #[test]
+    fn coverage_of_playthroughs() {
+        let dlg = Dialogue::example();
+        let coverage = dlg.coverage(&[vec![0, 0]]).unwrap();
+        assert_eq!(coverage.uncovered.into_iter().collect::<Vec<_>>(), vec![10]);
+        let coverage = dlg.coverage(&[vec![0, 0], vec![1]]).unwrap();
+        assert!(coverage.uncovered.is_empty());
+        assert_eq!(coverage.ratio(), 1.0);
+    }
+
+    #[test]
+    fn coverage_invalid_choice() {
+        let dlg = Dialogue::example();
+        assert_eq!(
+            dlg.coverage(&[vec![0], vec![5]]),
+            Err(CoverageError::InvalidChoice {
+                playthrough: 1,
+                entry: 7,
+                choice: 5
+            })
+        );
+    }
+}
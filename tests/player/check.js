@@ -0,0 +1,69 @@
+// Plays every .dlgtest file of this directory in the HTML player and compares the
+// transcripts, like `dialasm test` does with `Runner`. Run with `make player-test`.
+// Usage: node check.js <page exported with `dialasm export player.dlg --html`>
+const fs = require("fs"), path = require("path"), vm = require("vm");
+
+const html = fs.readFileSync(process.argv[2], "utf8");
+const data = html.match(/<script id="dialogue" type="application\/json">([\s\S]*?)<\/script>/)[1];
+const code = html.match(/<script>\n([\s\S]*?)<\/script>/)[1];
+
+// Just enough of the DOM for the player to load.
+const stub = () => ({
+  appendChild() {}, replaceChildren() {}, addEventListener() {}, focus() {}, dataset: {},
+  set textContent(v) {},
+});
+const context = {
+  document: {
+    getElementById: id => id === "dialogue" ? { textContent: data } : stub(),
+    createElement: stub, createTextNode: stub, body: {},
+  },
+  window: { scrollTo() {} },
+};
+vm.createContext(context);
+vm.runInContext(code, context);
+
+function parse(src) {
+  const lines = src.split("\n");
+  const separator = lines.findIndex(l => l.trim() === "---");
+  const test = { start: null, choices: [], expected: lines.slice(separator + 1).map(l => l.trimEnd()) };
+  for (const line of lines.slice(0, separator)) {
+    const [key, value] = line.split(/:(.*)/).map(s => s && s.trim());
+    if (key === "start") test.start = value;
+    if (key === "choices") test.choices = value.split(",").map(c => Number(c) - 1);
+  }
+  while (test.expected.length && !test.expected[test.expected.length - 1]) test.expected.pop();
+  return test;
+}
+
+function transcript(test) {
+  context.start = test.start;
+  vm.runInContext("state = { pointer: start === null ? 0 : label(start), shown: false, names: {} };", context);
+  const choices = [...test.choices], result = [];
+  for (;;) {
+    const event = vm.runInContext("step()", context);
+    if (event.kind === "line") {
+      result.push(event.speakers.join(" & ") + ": " + vm.runInContext("plainText", context)(event.spans));
+    } else if (event.kind === "choices" && choices.length) {
+      context.option = choices.shift();
+      result.push("> " + event.options[context.option]);
+      vm.runInContext("state.pointer = label(entries[state.pointer].options[option].label); state.shown = false;", context);
+    } else {
+      return result;
+    }
+  }
+}
+
+let failed = 0;
+for (const file of fs.readdirSync(__dirname).filter(f => f.endsWith(".dlgtest")).sort()) {
+  const test = parse(fs.readFileSync(path.join(__dirname, file), "utf8"));
+  const actual = transcript(test);
+  const line = [...Array(Math.max(test.expected.length, actual.length)).keys()]
+    .find(i => test.expected[i] !== actual[i]);
+  if (line === undefined) {
+    console.log(`${file}: ok`);
+  } else {
+    failed += 1;
+    console.log(`${file}: line ${line + 1}: expected ${JSON.stringify(test.expected[line])}, got ${JSON.stringify(actual[line])}`);
+  }
+}
+process.exit(failed ? 1 : 0);
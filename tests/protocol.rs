@@ -0,0 +1,76 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod protocol {
+    use super::*;
+    use dialasm::{
+        Dialogue, Runner,
+        protocol::{Message, execute_line},
+    };
+
+    fn run(runner: &mut Runner<'_>, line: &str) -> String {
+        serde_json::to_string(&execute_line(runner, line)).unwrap()
+    }
+
+    #[test]
+    fn advance_and_choose() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(
+            run(&mut runner, r#"{"cmd": "goto", "label": "last"}"#),
+            r#"{"event":"line","index":11,"id":"last-6510cc78","handles":["l","m"],"names":["l","m"],"text":"Now, last choice... well, you only have one."}"#
+        );
+        assert_eq!(
+            run(&mut runner, r#"{"cmd": "advance"}"#),
+            r#"{"event":"choices","index":12,"id":"last-b64f50c3","options":["Byeee!"]}"#
+        );
+        assert_eq!(
+            run(&mut runner, r#"{"cmd": "choose", "index": 0}"#),
+            r#"{"event":"line","index":13,"id":"end-5ca6e09d","handles":["m"],"names":["m"],"text":"Goodbye!"}"#
+        );
+        assert_eq!(
+            run(&mut runner, r#"{"cmd": "advance"}"#),
+            r#"{"event":"end"}"#
+        );
+    }
+
+    #[test]
+    fn save_and_load_state() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        run(&mut runner, r#"{"cmd": "advance"}"#);
+        let line = run(&mut runner, r#"{"cmd": "advance"}"#);
+        let Message::State { state } = execute_line(&mut runner, r#"{"cmd": "get_state"}"#) else {
+            panic!("Expected state");
+        };
+        assert_eq!(state.pointer, 3);
+        run(&mut runner, r#"{"cmd": "advance"}"#);
+        let command = format!(
+            r#"{{"cmd": "load_state", "state": {}}}"#,
+            serde_json::to_string(&state).unwrap()
+        );
+        assert_eq!(run(&mut runner, &command), line);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(
+            execute_line(&mut runner, r#"{"cmd": "choose", "index": 0}"#),
+            Message::Error {
+                message: String::from("There is no choice to make")
+            }
+        );
+        assert_eq!(
+            execute_line(&mut runner, r#"{"cmd": "goto", "label": "nowhere"}"#),
+            Message::Error {
+                message: String::from("Undefined label 'nowhere'")
+            }
+        );
+        assert!(matches!(
+            execute_line(&mut runner, "not json"),
+            Message::Error { .. }
+        ));
+    }
+}
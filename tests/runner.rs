@@ -0,0 +1,185 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod runner {
+    use super::*;
+    use dialasm::{Dialogue, Event, HistoryEntry, RunError, Runner, Value};
+
+    #[test]
+    fn empty_dialogue_ends() {
+        let dlg = Dialogue::parse("").unwrap();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(runner.advance(), Event::End);
+        assert!(runner.is_finished());
+    }
+
+    #[test]
+    fn lines_have_speaker_names() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        let Event::Line(line) = runner.advance() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.index, 2);
+        assert_eq!(line.speaker(), "");
+        runner.advance();
+        runner.advance();
+        let Event::Line(line) = runner.advance() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.handles, vec![String::from("m"), String::from("l")]);
+        assert_eq!(line.speaker(), "Maria & Leon");
+        assert_eq!(runner.current(), Event::Line(line));
+    }
+
+    #[test]
+    fn runner_stays_at_choice() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        runner.goto("last").unwrap();
+        runner.advance();
+        let Event::Choices(choices) = runner.advance() else {
+            panic!("Expected choices");
+        };
+        assert_eq!(choices.options, vec![String::from("Byeee!")]);
+        assert_eq!(runner.advance(), Event::Choices(choices));
+        assert_eq!(runner.choose(1), Err(RunError::InvalidChoice(1)));
+        runner.choose(0).unwrap();
+        let Event::Line(line) = runner.advance() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.text, "Goodbye!");
+        assert_eq!(runner.advance(), Event::End);
+        assert_eq!(runner.choose(0), Err(RunError::NotAtChoice));
+    }
+
+    #[test]
+    fn goto_undefined_label() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(
+            runner.goto("nowhere"),
+            Err(RunError::UndefinedLabel(String::from("nowhere")))
+        );
+    }
+
+    #[test]
+    fn endless_jump_loop_ends() {
+        let dlg = Dialogue::parse("a: jump a;").unwrap();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(runner.advance(), Event::End);
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        runner.set_variable("gold", 10);
+        while !matches!(runner.advance(), Event::Choices(_)) {}
+        runner.choose(1).unwrap();
+        let line = runner.advance();
+        let saved = serde_json::to_string(&runner.state()).unwrap();
+
+        let mut restored = Runner::new(&dlg);
+        restored
+            .load_state(serde_json::from_str(&saved).unwrap())
+            .unwrap();
+        assert_eq!(restored.resume(), line);
+        assert_eq!(restored.variable("gold"), Some(&Value::Number(10.0)));
+        assert_eq!(restored.speakers()["m"], "Maria");
+        assert!(restored.is_visited(7, 1));
+        assert!(!restored.is_visited(7, 0));
+    }
+
+    #[test]
+    fn renamed_id_changes_hash() {
+        let dlg = Dialogue::parse("? (\"Yes\": a | \"No\": a) #id:question; a:").unwrap();
+        let renamed = Dialogue::parse("? (\"Yes\": a | \"No\": a) #id:answer; a:").unwrap();
+        let generated = Dialogue::parse("? (\"Yes\": a | \"No\": a); a:").unwrap();
+        assert_ne!(dlg.content_hash(), renamed.content_hash());
+        assert_ne!(dlg.content_hash(), generated.content_hash());
+    }
+
+    #[test]
+    fn state_of_changed_dialogue_is_rejected() {
+        let dlg = Dialogue::parse(": \"Hello.\"; : \"Bye.\";").unwrap();
+        let changed = Dialogue::parse(": \"Hi.\"; : \"Bye.\";").unwrap();
+        assert_ne!(dlg.content_hash(), changed.content_hash());
+        let state = Runner::new(&dlg).state();
+        let mut runner = Runner::new(&changed);
+        assert!(matches!(
+            runner.load_state(state),
+            Err(RunError::DialogueChanged { .. })
+        ));
+    }
+
+    #[test]
+    fn history_keeps_lines_and_choices() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        while !matches!(runner.advance(), Event::Choices(_)) {}
+        runner.advance();
+        runner.choose(1).unwrap();
+        runner.advance();
+        let history: Vec<&HistoryEntry> = runner.history().collect();
+        assert_eq!(history.len(), 7);
+        assert!(matches!(
+            history[5],
+            HistoryEntry::Choice {
+                picked: Some(1),
+                ..
+            }
+        ));
+        assert!(
+            matches!(history[6], HistoryEntry::Line(l) if l.text == "Certainly better choice.")
+        );
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        runner.set_history_limit(2);
+        for _ in 0..4 {
+            runner.advance();
+        }
+        let history: Vec<&HistoryEntry> = runner.history().collect();
+        assert_eq!(history.len(), 2);
+        assert!(matches!(history[0], HistoryEntry::Line(l) if l.index == 4));
+    }
+
+    #[test]
+    fn rewind_restores_state() {
+        let dlg = Dialogue::parse(
+            "@m = \"Maria\"; @m: \"One\"; @m = \"Mary\"; @m: \"Two\"; ? \"Go\": end; end: @m: \"Three\";",
+        )
+        .unwrap();
+        let mut runner = Runner::new(&dlg);
+        runner.advance();
+        runner.set_variable("x", 1);
+        runner.advance();
+        runner.set_variable("x", 2);
+        runner.advance();
+        runner.choose(0).unwrap();
+        runner.advance();
+
+        let Ok(Event::Choices(_)) = runner.rewind(1) else {
+            panic!("Expected choices");
+        };
+        assert!(!runner.is_visited(4, 0));
+        assert_eq!(runner.variable("x"), Some(&Value::Number(2.0)));
+
+        let Ok(Event::Line(line)) = runner.rewind(2) else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.speaker(), "Maria");
+        assert_eq!(runner.variable("x"), None);
+        assert_eq!(runner.history().count(), 1);
+        assert_eq!(runner.rewind(1), Err(RunError::HistoryTooShort(1)));
+
+        let Event::Line(line) = runner.advance() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.speaker(), "Mary");
+    }
+}
@@ -0,0 +1,60 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod seen {
+    use super::*;
+    use dialasm::{Dialogue, Event, Runner, SeenSet};
+
+    #[test]
+    fn shown_lines_are_seen() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        runner.advance();
+        runner.advance();
+        assert_eq!(runner.seen().len(), 2);
+        assert!(runner.seen().contains(dlg.id(2).unwrap()));
+        assert!(!runner.seen().contains(dlg.id(4).unwrap()));
+    }
+
+    #[test]
+    fn skip_stops_at_unseen_line() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        runner.advance();
+        runner.advance();
+        let seen = runner.seen().clone();
+
+        let mut runner = Runner::new(&dlg);
+        runner.set_seen(seen);
+        let Event::Line(line) = runner.skip_seen() else {
+            panic!("Expected line");
+        };
+        assert_eq!(line.index, 4);
+        assert_eq!(runner.history().count(), 3);
+    }
+
+    #[test]
+    fn skip_stops_at_choice() {
+        let dlg = Dialogue::example();
+        let mut runner = Runner::new(&dlg);
+        while !matches!(runner.advance(), Event::Choices(_)) {}
+        let seen = runner.seen().clone();
+
+        let mut runner = Runner::new(&dlg);
+        runner.set_seen(seen);
+        assert!(matches!(runner.skip_seen(), Event::Choices(c) if c.index == 7));
+        runner.choose(0).unwrap();
+        assert!(matches!(runner.skip_seen(), Event::Line(l) if l.index == 8));
+    }
+
+    #[test]
+    fn seen_set_serialization() {
+        let mut seen = SeenSet::new();
+        assert!(seen.insert("b"));
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        let json = serde_json::to_string(&seen).unwrap();
+        assert_eq!(json, r#"["a","b"]"#);
+        assert_eq!(serde_json::from_str::<SeenSet>(&json).unwrap(), seen);
+    }
+}
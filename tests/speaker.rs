@@ -0,0 +1,123 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod speaker {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use dialasm::{Dialogue, DialogueEntry, ParseError, Runner, Speaker, Value};
+
+    const INPUT: &str = "@m = \"Maria\" { color: \"#f0a\", portrait: \"maria.png\", pitch: 1.2 };
+        @l = \"Leon\";
+        start:
+        @m: \"Hi!\";
+        @m { portrait: \"maria_angry.png\" };
+        @m: \"Grr!\";";
+
+    fn attributes(attributes: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_attributes() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(
+            dlg[0],
+            DialogueEntry::NameChange(String::from("m"), String::from("Maria"))
+        );
+        assert_eq!(
+            dlg[1],
+            DialogueEntry::AttributeChange(
+                String::from("m"),
+                attributes(&[
+                    ("color", Value::from("#f0a")),
+                    ("portrait", Value::from("maria.png")),
+                    ("pitch", Value::Number(1.2)),
+                ])
+            )
+        );
+        assert_eq!(dlg.label("start"), Some(3));
+        assert_eq!(
+            dlg[4],
+            DialogueEntry::AttributeChange(
+                String::from("m"),
+                attributes(&[("portrait", Value::from("maria_angry.png"))])
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_attributes() {
+        assert!(matches!(
+            Dialogue::parse("@m { color: \"red\" };"),
+            Err(ParseError::UndefinedSpeaker(h)) if h == "m"
+        ));
+        assert!(matches!(
+            Dialogue::parse("@m = \"Maria\" { color: \"red\", color: \"blue\" };"),
+            Err(ParseError::DuplicateAttribute(a)) if a == "color"
+        ));
+    }
+
+    #[test]
+    fn dialogue_speakers() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        assert_eq!(
+            dlg.speakers(),
+            vec![
+                Speaker {
+                    handle: String::from("l"),
+                    name: Some(String::from("Leon")),
+                    attributes: BTreeMap::new(),
+                },
+                Speaker {
+                    handle: String::from("m"),
+                    name: Some(String::from("Maria")),
+                    attributes: attributes(&[
+                        ("color", Value::from("#f0a")),
+                        ("portrait", Value::from("maria.png")),
+                        ("pitch", Value::Number(1.2)),
+                    ]),
+                },
+            ]
+        );
+        assert_eq!(dlg.speaker("x"), None);
+    }
+
+    #[test]
+    fn runner_attributes() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut runner = Runner::new(&dlg);
+        assert_eq!(runner.speaker("m"), None);
+        runner.advance();
+        assert_eq!(
+            runner.attribute("m", "portrait"),
+            Some(&Value::from("maria.png"))
+        );
+        let state = runner.state();
+        runner.advance();
+        assert_eq!(
+            runner.attribute("m", "portrait"),
+            Some(&Value::from("maria_angry.png"))
+        );
+        assert_eq!(runner.attribute("m", "color"), Some(&Value::from("#f0a")));
+        assert_eq!(
+            runner.speaker("l").map(|s| s.attributes),
+            Some(BTreeMap::new())
+        );
+
+        runner.rewind(1).unwrap();
+        assert_eq!(
+            runner.attribute("m", "portrait"),
+            Some(&Value::from("maria.png"))
+        );
+        runner.advance();
+        runner.load_state(state).unwrap();
+        assert_eq!(
+            runner.attribute("m", "portrait"),
+            Some(&Value::from("maria.png"))
+        );
+    }
+}
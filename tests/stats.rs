@@ -0,0 +1,73 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod stats {
+    use super::*;
+    use dialasm::{Dialogue, SpeakerStats};
+
+    #[test]
+    fn empty_dialogue_stats() {
+        let stats = Dialogue::parse("").unwrap().stats();
+        assert!(stats.speakers.is_empty());
+        assert_eq!(stats.lines, 0);
+        assert_eq!(stats.words, 0);
+        assert_eq!(stats.choices, 0);
+        assert_eq!(stats.branching_factor, 0.0);
+        assert_eq!(stats.reading_time, 0.0);
+    }
+
+    #[test]
+    fn example_dialogue_stats() {
+        let stats = Dialogue::example().stats();
+        assert_eq!(
+            stats.speakers,
+            vec![
+                SpeakerStats {
+                    handle: None,
+                    name: None,
+                    lines: 1,
+                    words: 8
+                },
+                SpeakerStats {
+                    handle: Some(String::from("l")),
+                    name: Some(String::from("Leon")),
+                    lines: 4,
+                    words: 23
+                },
+                SpeakerStats {
+                    handle: Some(String::from("m")),
+                    name: Some(String::from("Maria")),
+                    lines: 6,
+                    words: 29
+                },
+            ]
+        );
+        assert_eq!(stats.lines, 9);
+        assert_eq!(stats.words, 45);
+        assert_eq!(stats.choices, 2);
+        assert_eq!(stats.options, 3);
+        assert_eq!(stats.choice_words, 7);
+        assert_eq!(stats.branching_factor, 1.5);
+        assert_eq!(stats.labels, 4);
+    }
+
+    #[test]
+    fn first_name_is_reported() {
+        const INPUT: &str = "@m = \"Maria\"; @m: \"One two\"; @m = \"Mary\"; @m: \"Three\";";
+        let stats = Dialogue::parse(INPUT).unwrap().stats();
+        assert_eq!(stats.speakers.len(), 1);
+        assert_eq!(stats.speakers[0].name.as_deref(), Some("Maria"));
+        assert_eq!(stats.speakers[0].lines, 2);
+        assert_eq!(stats.speakers[0].words, 3);
+    }
+
+    #[test]
+    fn markup_is_not_counted() {
+        const INPUT: &str =
+            "@m = \"Maria\"; @m: \"I [b]really[/b] mean it. [wait=0.5] {$gold} gold\";
+            ? \"[i]Fine[/i] [wait=1]\": a; a:";
+        let stats = Dialogue::parse(INPUT).unwrap().stats();
+        assert_eq!(stats.words, 6);
+        assert_eq!(stats.choice_words, 1);
+    }
+}
@@ -0,0 +1,116 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod status {
+    use super::*;
+    use dialasm::{Dialogue, StringTable, TranslationStatus, source_hash};
+
+    const OLD: &str = "@m = \"Maria\";
+        @m: \"Hello.\" #id:hello;
+        @m: \"How are you?\" #id:how;
+        ? (\"Fine\": a | \"Bad\": a) #id:mood;
+        a:";
+
+    const NEW: &str = "@m = \"Maria\";
+        @m: \"Hello there.\" #id:hello;
+        ? (\"Fine\": a | \"Bad\": a) #id:mood;
+        @m: \"Bye.\" #id:bye;
+        a:";
+
+    fn translate(dlg: &Dialogue) -> StringTable {
+        let mut table = dlg.strings();
+        table.language = Some(String::from("de"));
+        for entry in &mut table.entries {
+            entry.text = entry.text.to_uppercase();
+        }
+        table
+    }
+
+    #[test]
+    fn hashes() {
+        let table = Dialogue::parse(OLD).unwrap().strings();
+        let hello = table.get("hello").unwrap();
+        assert_eq!(hello.source_hash, Some(source_hash("Hello.")));
+        assert_eq!(source_hash("Hello."), source_hash("Hello."));
+        assert_ne!(source_hash("Hello."), source_hash("Hello there."));
+        assert_eq!(source_hash("Hello.").len(), 8);
+    }
+
+    #[test]
+    fn changes() {
+        let translation = translate(&Dialogue::parse(OLD).unwrap());
+        let old = Dialogue::parse(OLD).unwrap();
+        assert!(old.translation_status(&translation).is_up_to_date());
+
+        let new = Dialogue::parse(NEW).unwrap();
+        assert_eq!(
+            new.translation_status(&translation),
+            TranslationStatus {
+                language: Some(String::from("de")),
+                new: vec![String::from("bye")],
+                changed: vec![String::from("hello")],
+                removed: vec![String::from("how")],
+            }
+        );
+    }
+
+    #[test]
+    fn without_hashes() {
+        let mut translation = translate(&Dialogue::parse(OLD).unwrap());
+        for entry in &mut translation.entries {
+            entry.source_hash = None;
+        }
+        let status = Dialogue::parse(NEW)
+            .unwrap()
+            .translation_status(&translation);
+        assert!(status.changed.is_empty());
+        assert_eq!(status.new, vec![String::from("bye")]);
+    }
+
+    #[test]
+    fn gettext() {
+        let old = Dialogue::parse(OLD).unwrap();
+        let template = old.strings().to_pot("intro.dlg");
+        let po = template
+            .replace(
+                "msgid \"Hello.\"\nmsgstr \"\"",
+                "msgid \"Hello.\"\nmsgstr \"Hallo.\"",
+            )
+            .replace(
+                "msgid \"Fine\"\nmsgstr \"\"",
+                "msgid \"Fine\"\nmsgstr \"Gut\"",
+            );
+        let translation = StringTable::parse_po(&po).unwrap();
+        assert_eq!(translation.entries.len(), 2);
+
+        let status = Dialogue::parse(NEW)
+            .unwrap()
+            .translation_status(&translation);
+        assert_eq!(status.changed, vec![String::from("hello")]);
+        assert_eq!(
+            status.new,
+            vec![
+                String::from("@m"),
+                String::from("mood.1"),
+                String::from("bye")
+            ]
+        );
+        assert!(status.removed.is_empty());
+    }
+
+    #[test]
+    fn xliff() {
+        let old = Dialogue::parse(OLD).unwrap();
+        let translation = translate(&old);
+        let xliff = old
+            .strings()
+            .to_xliff("intro.dlg", "en", Some(&translation));
+        let parsed = StringTable::parse_xliff(&xliff).unwrap();
+        assert_eq!(parsed.language, Some(String::from("de")));
+        assert_eq!(parsed.text("@m"), Some("MARIA"));
+
+        let status = Dialogue::parse(NEW).unwrap().translation_status(&parsed);
+        assert_eq!(status.changed, vec![String::from("hello")]);
+        assert_eq!(status.removed, vec![String::from("how")]);
+    }
+}
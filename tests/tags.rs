@@ -0,0 +1,83 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod tags {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use dialasm::{Dialogue, Event, ParseError, Runner, protocol};
+
+    const INPUT: &str = "@m = \"Maria\";
+        @m: \"Hi!\" #emotion:happy #vo:maria_001;
+        @m: \"Where to?\" #id:where #vo:maria_002;
+        ? (\"Left\": a | \"Right\": a) #anim:look-around;
+        a: : \"Nothing here.\";";
+
+    #[test]
+    fn parse_tags() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        assert_eq!(
+            dlg.tags(1),
+            Some(&BTreeMap::from([
+                (String::from("emotion"), String::from("happy")),
+                (String::from("vo"), String::from("maria_001")),
+            ]))
+        );
+        assert_eq!(dlg.id(2), Some("where"));
+        assert_eq!(dlg.tag(2, "vo"), Some("maria_002"));
+        assert_eq!(dlg.tag(2, "emotion"), None);
+        assert_eq!(dlg.tag(3, "anim"), Some("look-around"));
+        assert_eq!(dlg.tags(4), Some(&BTreeMap::new()));
+        assert_eq!(
+            dlg.tagged("vo").collect::<Vec<_>>(),
+            vec![(1, "maria_001"), (2, "maria_002")]
+        );
+    }
+
+    #[test]
+    fn invalid_tags() {
+        assert!(matches!(
+            Dialogue::parse(": \"Hi!\" #vo:a #vo:b;"),
+            Err(ParseError::DuplicateTag(t)) if t == "vo"
+        ));
+        assert!(matches!(
+            Dialogue::parse(": \"Hi!\" #id:a #id:b;"),
+            Err(ParseError::DuplicateTag(t)) if t == "id"
+        ));
+        assert!(matches!(
+            Dialogue::parse(": \"Hi!\" #vo:;"),
+            Err(ParseError::InvalidProgram(_))
+        ));
+        assert!(matches!(
+            Dialogue::parse("jump a #vo:a; a:"),
+            Err(ParseError::InvalidProgram(_))
+        ));
+    }
+
+    #[test]
+    fn comments_after_tags() {
+        let dlg = Dialogue::parse(
+            ": \"Hi!\" #vo:a/* first */; ? \"Go\": a #id:go /* second */ #anim:wave; a:",
+        )
+        .unwrap();
+        assert_eq!(dlg.tag(0, "vo"), Some("a"));
+        assert_eq!(dlg.id(1), Some("go"));
+        assert_eq!(dlg.tag(1, "anim"), Some("wave"));
+    }
+
+    #[test]
+    fn runner_events() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut runner = Runner::new(&dlg);
+        match runner.advance() {
+            Event::Line(l) => assert_eq!(l.tags.get("emotion").unwrap(), "happy"),
+            e => panic!("Expected line, got {:?}", e),
+        }
+        runner.advance();
+        assert_eq!(
+            serde_json::to_string(&protocol::execute(&mut runner, protocol::Command::Advance))
+                .unwrap(),
+            r#"{"event":"choices","index":3,"id":"ec92bd26","options":["Left","Right"],"tags":{"anim":"look-around"}}"#
+        );
+    }
+}
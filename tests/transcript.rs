@@ -0,0 +1,82 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod transcript {
+    use super::*;
+    use dialasm::{Dialogue, TranscriptError, TranscriptMismatch, TranscriptTest, transcript};
+
+    const TEST: &str = "script: example.dlg
+choices: 2, 1
+---
+: This is a phrase told by... well, nobody.
+Maria: Hello, my name is Maria!
+Leon: Hello, my name is Leon.
+Maria & Leon: And we can talk together as well!
+Maria: Now, you pick where to go!
+> I pick B
+Leon: Certainly better choice.
+Leon & Maria: Now, last choice... well, you only have one.
+> Byeee!
+Maria: Goodbye!
+
+";
+
+    #[test]
+    fn parse_transcript_test() {
+        let test = TranscriptTest::parse(TEST).unwrap();
+        assert_eq!(test.script, "example.dlg");
+        assert_eq!(test.start, None);
+        assert_eq!(test.choices, vec![1, 0]);
+        assert_eq!(test.expected.len(), 10);
+    }
+
+    #[test]
+    fn transcript_test_passes() {
+        let test = TranscriptTest::parse(TEST).unwrap();
+        assert_eq!(test.run(&Dialogue::example()), Ok(None));
+    }
+
+    #[test]
+    fn transcript_test_fails() {
+        let test = TranscriptTest::parse(&TEST.replace("Goodbye", "Bye")).unwrap();
+        assert_eq!(
+            test.run(&Dialogue::example()),
+            Ok(Some(TranscriptMismatch {
+                line: 10,
+                expected: Some(String::from("Maria: Bye!")),
+                actual: Some(String::from("Maria: Goodbye!")),
+            }))
+        );
+    }
+
+    #[test]
+    fn transcript_stops_without_choices() {
+        let lines = transcript(&Dialogue::example(), Some("last"), &[]).unwrap();
+        assert_eq!(
+            lines,
+            vec![String::from(
+                "l & m: Now, last choice... well, you only have one."
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_transcript_tests() {
+        assert_eq!(
+            TranscriptTest::parse("script: a.dlg\n"),
+            Err(TranscriptError::MissingSeparator)
+        );
+        assert_eq!(
+            TranscriptTest::parse("choices: 1\n---\n"),
+            Err(TranscriptError::MissingScript)
+        );
+        assert_eq!(
+            TranscriptTest::parse("script: a.dlg\nchoices: 0\n---\n"),
+            Err(TranscriptError::InvalidChoice(String::from("0")))
+        );
+        assert_eq!(
+            TranscriptTest::parse("hello\n---\n"),
+            Err(TranscriptError::InvalidHeader(String::from("hello")))
+        );
+    }
+}
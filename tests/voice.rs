@@ -0,0 +1,108 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod voice {
+    use super::*;
+    use dialasm::{Dialogue, vo_file};
+
+    const INPUT: &str = "@m = \"Maria\";
+        @l = \"Leon\";
+        start:
+        @m: \"Say \\\"hi\\\", [b]{@l}[/b]! You have {$gold}.\" #id:hi #mood:happy;
+        (@m & @l): \"Together.\" #id:both;
+        : \"Nobody voices this.\" #id:narrator;
+        end:
+        @l: \"Bye,\nMaria.\" #id:bye;";
+
+    #[test]
+    fn actors() {
+        let script = Dialogue::parse(INPUT).unwrap().vo_script("ogg");
+        let handles: Vec<&str> = script.actors.iter().map(|a| a.handle.as_str()).collect();
+        assert_eq!(handles, vec!["l", "m"]);
+        let maria = &script.actors[1];
+        assert_eq!(maria.name.as_deref(), Some("Maria"));
+        let ids: Vec<&str> = maria.lines.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["hi", "both"]);
+        let ids: Vec<&str> = script.lines().map(|l| l.file.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["l/both.ogg", "l/bye.ogg", "m/hi.ogg", "m/both.ogg"]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let script = Dialogue::parse(INPUT).unwrap().vo_script("wav");
+        let hi = &script.actors[1].lines[0];
+        assert_eq!(hi.text, "Say \"hi\", Leon! You have {$gold}.");
+        assert_eq!(hi.file, vo_file("m", "hi", "wav"));
+        assert_eq!(hi.label.as_deref(), Some("start"));
+        assert_eq!(hi.line, Some(4));
+        assert_eq!(hi.tags.get("mood").map(String::as_str), Some("happy"));
+        assert!(hi.with.is_empty());
+        let both = &script.actors[0].lines[0];
+        assert_eq!(both.with, vec![String::from("Maria (@m)")]);
+    }
+
+    #[test]
+    fn stable_files() {
+        let old = Dialogue::parse(INPUT).unwrap().vo_script("wav");
+        let edited = INPUT.replace("Together.", "All together now.");
+        let new = Dialogue::parse(&edited).unwrap().vo_script("wav");
+        let files =
+            |s: &dialasm::VoScript| -> Vec<String> { s.lines().map(|l| l.file.clone()).collect() };
+        assert_eq!(files(&old), files(&new));
+        assert!(old.lines().all(|l| l.explicit_id));
+
+        // Without explicit id, the file is named by the generated one, which follows the text.
+        let input = "@m = \"Maria\"; @m: \"Hi!\";";
+        let old = Dialogue::parse(input).unwrap().vo_script("wav");
+        let new = Dialogue::parse(&input.replace("Hi!", "Hello!"))
+            .unwrap()
+            .vo_script("wav");
+        assert!(!old.actors[0].lines[0].explicit_id);
+        assert_ne!(files(&old), files(&new));
+    }
+
+    #[test]
+    fn documents() {
+        let script = Dialogue::parse(INPUT).unwrap().vo_script("wav");
+        let leon = &script.actors[0];
+        assert_eq!(leon.file_name(), "l.md");
+        assert_eq!(
+            leon.to_markdown(),
+            "# Leon (@l)
+
+2 lines
+
+## start
+
+### both
+
+- File: `l/both.wav`
+- With: Maria (@m)
+
+> Together.
+
+## end
+
+### bye
+
+- File: `l/bye.wav`
+
+> Bye,
+> Maria.
+"
+        );
+        assert_eq!(
+            script.manifest(),
+            "id,speaker,file,text
+both,l,l/both.wav,Together.
+bye,l,l/bye.wav,\"Bye,
+Maria.\"
+hi,m,m/hi.wav,\"Say \"\"hi\"\", Leon! You have {$gold}.\"
+both,m,m/both.wav,Together.
+"
+        );
+    }
+}
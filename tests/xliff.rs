@@ -0,0 +1,125 @@
+extern crate dialasm;
+
+#[cfg(test)]
+mod xliff {
+    use super::*;
+    use dialasm::{Dialogue, StringTable, XliffError, XliffReport};
+
+    const INPUT: &str = "@m = \"Maria\";
+        start:
+        @m: \"Say \\\"hi\\\" & go\" #id:hi;
+        ? (\"Left\": a | \"Right\": a) #id:way;
+        a:";
+
+    #[test]
+    fn export() {
+        let dlg = Dialogue::parse(INPUT).expect("Expected to parse valid input");
+        let xliff = dlg.strings().to_xliff("intro.dlg", "en", None);
+        assert!(xliff.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <xliff xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" version=\"2.0\" srcLang=\"en\">\n  \
+            <file id=\"f1\" original=\"intro.dlg\">\n    \
+            <unit id=\"name:m\">\n"
+        ));
+        assert!(xliff.contains(
+            "    <unit id=\"hi\">\n      \
+            <notes>\n        \
+            <note category=\"speaker\">Maria (@m)</note>\n        \
+            <note category=\"label\">start</note>\n        \
+            <note category=\"location\">intro.dlg:3</note>\n      \
+            </notes>\n      \
+            <segment>\n        \
+            <source>Say &quot;hi&quot; &amp; go</source>\n      \
+            </segment>\n    \
+            </unit>\n"
+        ));
+        assert!(xliff.contains("<note category=\"context\">Choice option</note>"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let mut translation = dlg.strings();
+        translation.language = Some(String::from("de"));
+        translation.entries[1].text = String::from("Sag \\\"hallo\\\" & geh");
+        let xliff = dlg
+            .strings()
+            .to_xliff("intro.dlg", "en", Some(&translation));
+        assert!(xliff.contains("trgLang=\"de\""));
+        assert!(xliff.contains("<target>Sag &quot;hallo&quot; &amp; geh</target>"));
+        let (table, report) = StringTable::from_xliff(&xliff, &dlg.strings()).unwrap();
+        assert_eq!(table, translation);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn report() {
+        let old = Dialogue::parse(INPUT).unwrap();
+        let mut translation = old.strings();
+        for entry in &mut translation.entries {
+            entry.text = entry.text.to_uppercase();
+        }
+        translation.entries.remove(2);
+        let xliff = old
+            .strings()
+            .to_xliff("intro.dlg", "en", Some(&translation));
+
+        let new = Dialogue::parse(
+            "@m = \"Maria\";
+            start:
+            @m: \"Say hello\" #id:hi;
+            @m: \"New line\" #id:new;
+            ? \"Right\": a #id:way;
+            a:",
+        )
+        .unwrap();
+        let (table, report) = StringTable::from_xliff(&xliff, &new.strings()).unwrap();
+        assert_eq!(
+            report,
+            XliffReport {
+                unknown: vec![String::from("way.1")],
+                stale: vec![String::from("hi")],
+                missing: vec![String::from("new"), String::from("way.0")],
+            }
+        );
+        assert_eq!(table.text("hi"), Some("SAY \\\"HI\\\" & GO"));
+        assert_eq!(table.text("new"), Some("New line"));
+        assert_eq!(table.text("@m"), Some("MARIA"));
+    }
+
+    #[test]
+    fn unknown_units_without_target() {
+        let dlg = Dialogue::parse(INPUT).unwrap();
+        let xliff = "<xliff xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" version=\"2.0\">\
+            <file id=\"f\">\
+            <unit id=\"gone\"><segment><source>Old</source><target></target></segment></unit>\
+            <unit id=\"hi\"><segment><source>Say \\\"hi\\\" &amp; go</source></segment></unit>\
+            </file></xliff>";
+        let (table, report) = StringTable::from_xliff(xliff, &dlg.strings()).unwrap();
+        assert_eq!(report.unknown, vec![String::from("gone")]);
+        assert_eq!(table, dlg.strings());
+    }
+
+    #[test]
+    fn invalid_documents() {
+        let source = StringTable::default();
+        assert!(matches!(
+            StringTable::from_xliff("<xliff>", &source),
+            Err(XliffError::Xml(_))
+        ));
+        assert!(matches!(
+            StringTable::from_xliff("<xliff version=\"1.2\"/>", &source),
+            Err(XliffError::NotXliff)
+        ));
+        let unit = "<unit id=\"a\"><segment><source>A</source></segment></unit>";
+        let document = format!(
+            "<xliff xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" version=\"2.0\">\
+            <file id=\"f\">{}{}</file></xliff>",
+            unit, unit
+        );
+        assert!(matches!(
+            StringTable::from_xliff(&document, &source),
+            Err(XliffError::DuplicateUnit(id)) if id == "a"
+        ));
+    }
+}